@@ -1,28 +1,222 @@
-use std::fs::read_to_string;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind};
 
-pub fn day_1() -> (String, String) {
-    let f = read_to_string("input/day1.txt").unwrap();
+// Reads the input a line at a time and never holds more than one elf, so it copes with inputs of
+// any size. Both line endings work, and any number of blank lines count as a single break.
+
+// One elf's calories, numbered from 0 in input order
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Elf {
+    index: usize,
+    total: u64,
+}
+
+// More calories is greater. On a tie the earlier elf is greater, so it's the one kept.
+impl Ord for Elf {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.total
+            .cmp(&other.total)
+            .then(other.index.cmp(&self.index))
+    }
+}
 
-    let mut cals = vec![];
-    let mut run = vec![];
+impl PartialOrd for Elf {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-    for l in f.lines() {
-        if let Ok(i) = str::parse::<u32>(l) {
-            run.push(i);
-        } else {
-            cals.push(run);
-            run = vec![];
+struct Elves<R> {
+    reader: R,
+    line: String,
+    line_number: usize,
+    index: usize,
+}
+
+impl<R: BufRead> Elves<R> {
+    fn new(reader: R) -> Elves<R> {
+        Elves {
+            reader,
+            line: String::new(),
+            line_number: 0,
+            index: 0,
         }
     }
+}
 
-    cals.push(run);
+impl<R: BufRead> Iterator for Elves<R> {
+    type Item = std::io::Result<Elf>;
 
-    let mut totals: Vec<u32> = cals.into_iter().map(|c| c.into_iter().sum()).collect();
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut total = None;
 
-    totals.sort();
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => break,
+                Ok(_) => self.line_number += 1,
+                Err(e) => return Some(Err(e)),
+            }
 
-    let most = format!("{}", totals.last().unwrap());
-    let most_3 = format!("{}", totals.iter().rev().take(3).sum::<u32>());
+            let l = self.line.trim();
+            if l.is_empty() {
+                if total.is_some() {
+                    break;
+                }
+                continue;
+            }
 
-    (most, most_3)
+            let Ok(n) = l.parse::<u64>() else {
+                let msg = format!("line {}: {l:?} isn't a calorie count", self.line_number);
+                return Some(Err(Error::new(ErrorKind::InvalidData, msg)));
+            };
+            total = Some(total.unwrap_or(0) + n);
+        }
+
+        let elf = Elf {
+            index: self.index,
+            total: total?,
+        };
+        self.index += 1;
+        Some(Ok(elf))
+    }
+}
+
+type OnElf<'a> = Box<dyn FnMut(Elf) + 'a>;
+
+// Answers questions about elves as they stream past. The top n are kept in a min heap, so each
+// elf costs at most a log n swap, rather than sorting them all at the end.
+struct Aggregator<'a> {
+    n: usize,
+    top: BinaryHeap<Reverse<Elf>>,
+    // Told about each elf over the threshold as it's read, so they're never all held at once
+    above: Option<(u64, OnElf<'a>)>,
+    count: usize,
+}
+
+impl<'a> Aggregator<'a> {
+    fn new(n: usize) -> Aggregator<'a> {
+        Aggregator {
+            n,
+            top: BinaryHeap::with_capacity(n + 1),
+            above: None,
+            count: 0,
+        }
+    }
+
+    // Also passes every elf carrying more than `threshold` calories to `each`, in input order
+    fn above(mut self, threshold: u64, each: impl FnMut(Elf) + 'a) -> Aggregator<'a> {
+        self.above = Some((threshold, Box::new(each)));
+        self
+    }
+
+    fn push(&mut self, elf: Elf) {
+        self.count += 1;
+
+        if let Some((threshold, each)) = &mut self.above {
+            if elf.total > *threshold {
+                each(elf);
+            }
+        }
+
+        if self.top.len() < self.n {
+            self.top.push(Reverse(elf));
+        } else if self.top.peek().is_some_and(|Reverse(least)| elf > *least) {
+            self.top.pop();
+            self.top.push(Reverse(elf));
+        }
+    }
+
+    fn read(mut self, elves: impl Iterator<Item = std::io::Result<Elf>>) -> std::io::Result<Self> {
+        for elf in elves {
+            self.push(elf?);
+        }
+        Ok(self)
+    }
+
+    // The top n, most calories first
+    fn top(&self) -> Vec<Elf> {
+        let mut out = self.top.iter().map(|Reverse(e)| *e).collect::<Vec<Elf>>();
+        out.sort_by(|a, b| b.cmp(a));
+        out
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+}
+
+// The most calories one elf carries, and the most three carry between them. With no elves
+// nobody carries anything, and with fewer than three the top three is all of them.
+fn solve(reader: impl BufRead) -> std::io::Result<(u64, u64)> {
+    let top = Aggregator::new(3).read(Elves::new(reader))?.top();
+
+    let most = top.first().map_or(0, |e| e.total);
+    let most_3 = top.iter().map(|e| e.total).sum::<u64>();
+
+    Ok((most, most_3))
+}
+
+// Streams every elf carrying more than `threshold` calories to `each`, as its index and total,
+// and returns how many elves there were in all
+pub fn elves_above(threshold: u64, mut each: impl FnMut(usize, u64)) -> std::io::Result<usize> {
+    let f = BufReader::new(File::open("input/day1.txt")?);
+
+    let agg = Aggregator::new(0).above(threshold, |e| each(e.index, e.total));
+    Ok(agg.read(Elves::new(f))?.count())
+}
+
+pub fn day_1() -> (String, String) {
+    let f = BufReader::new(File::open("input/day1.txt").unwrap());
+
+    let (most, most_3) = solve(f).unwrap();
+
+    (format!("{most}"), format!("{most_3}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aggregate() {
+        let input =
+            "1000\r\n2000\r\n\r\n4000\r\n\r\n\r\n5000\r\n6000\r\n\r\n7000\n8000\n\n3000\n\n\n";
+
+        let elves = Elves::new(input.as_bytes())
+            .collect::<std::io::Result<Vec<Elf>>>()
+            .unwrap();
+        let totals = elves.iter().map(|e| (e.index, e.total)).collect::<Vec<_>>();
+        assert_eq!(
+            totals,
+            [(0, 3000), (1, 4000), (2, 11000), (3, 15000), (4, 3000)]
+        );
+
+        let mut above = vec![];
+        let (top, count) = {
+            let agg = Aggregator::new(3)
+                .above(3500, |e| above.push(e.index))
+                .read(Elves::new(input.as_bytes()))
+                .unwrap();
+            let top = agg.top().iter().map(|e| e.index).collect::<Vec<_>>();
+            (top, agg.count())
+        };
+        assert_eq!((top, above, count), (vec![3, 2, 1], vec![1, 2, 3], 5));
+
+        // Ties go to the earlier elf
+        let tied = Aggregator::new(1).read(Elves::new("5\n\n5\n".as_bytes()));
+        assert_eq!(tied.unwrap().top()[0].index, 0);
+
+        assert_eq!(solve(input.as_bytes()).unwrap(), (15000, 30000));
+        assert_eq!(solve("".as_bytes()).unwrap(), (0, 0));
+        assert_eq!(solve("\n\n7\n".as_bytes()).unwrap(), (7, 7));
+
+        let err = Elves::new("1\n2\nthree\n".as_bytes()).next().unwrap();
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "line 3: \"three\" isn't a calorie count"
+        );
+    }
 }
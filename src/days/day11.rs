@@ -1,113 +1,126 @@
+use crate::utils::num::{Int, Integer, NResult};
 use std::fs::read_to_string;
 
 #[derive(Clone)]
-enum MonkeyOp {
+enum MonkeyOp<N> {
     Square,
-    Add(usize),
-    Multiply(usize),
+    Add(N),
+    Multiply(N),
 }
 
 #[derive(Clone)]
-struct Item {
+struct Item<N> {
     owner: usize,
-    worry: usize,
+    worry: N,
 }
 
 #[derive(Clone)]
-struct Monkey {
+struct Monkey<N> {
     inspected_count: usize,
     num: usize,
-    op: MonkeyOp,
-    route: [usize; 3],
+    op: MonkeyOp<N>,
+    test: N,
+    route: [usize; 2],
 }
 
-impl Monkey {
-    fn run(&mut self, divisor: usize, co_prime: usize, items: &mut [Item]) {
+impl<N: Integer> Monkey<N> {
+    fn run(&mut self, limits: &Limits<N>, items: &mut [Item<N>]) -> NResult<()> {
         for m in items {
             if m.owner == self.num {
                 self.inspected_count += 1;
 
-                let mut new_level = match self.op {
-                    MonkeyOp::Square => m.worry * m.worry,
-                    MonkeyOp::Add(n) => m.worry + n,
-                    MonkeyOp::Multiply(n) => m.worry * n,
-                };
+                let mut new_level = match &self.op {
+                    MonkeyOp::Square => m.worry.mul(&m.worry),
+                    MonkeyOp::Add(n) => m.worry.add(n),
+                    MonkeyOp::Multiply(n) => m.worry.mul(n),
+                }
+                .map_err(|e| e.context(format!("monkey {} inspected an item", self.num)))?;
 
-                if divisor != 1 {
-                    new_level /= divisor
+                if let Some(divisor) = &limits.divisor {
+                    new_level = new_level.div(divisor)?;
                 }
 
-                if new_level > 1_000_000_000 {
-                    new_level %= co_prime;
+                if new_level > limits.reduce_above {
+                    new_level = new_level.rem(&limits.co_prime)?;
                 }
 
-                let target = if new_level % self.route[0] == 0 {
-                    self.route[1]
+                let target = if new_level.rem(&self.test)?.is_zero() {
+                    self.route[0]
                 } else {
-                    self.route[2]
+                    self.route[1]
                 };
 
                 m.worry = new_level;
                 m.owner = target;
             }
         }
+        Ok(())
     }
 }
 
-struct MonkeyTroop {
-    divisor: usize,
-    co_prime: usize,
-    items: Vec<Item>,
-    monkeys: Vec<Monkey>,
+// Worry levels only matter modulo the product of every monkey's test, so they're reduced by it
+// once they get big. Dividing by three in part one keeps them small anyway.
+#[derive(Clone)]
+struct Limits<N> {
+    divisor: Option<N>,
+    co_prime: N,
+    reduce_above: N,
 }
 
-impl MonkeyTroop {
-    fn run(&mut self) {
+struct MonkeyTroop<N> {
+    limits: Limits<N>,
+    items: Vec<Item<N>>,
+    monkeys: Vec<Monkey<N>>,
+}
+
+impl<N: Integer> MonkeyTroop<N> {
+    fn run(&mut self) -> NResult<()> {
         for n in 0..self.monkeys.len() {
-            self.monkeys[n].run(self.divisor, self.co_prime, &mut self.items);
+            self.monkeys[n].run(&self.limits, &mut self.items)?;
         }
+        Ok(())
     }
 
-    fn monkey_business(&self) -> usize {
+    fn monkey_business(&self) -> NResult<N> {
         let mut inspection_counts: Vec<usize> =
             self.monkeys.iter().map(|m| m.inspected_count).collect();
 
         inspection_counts.sort();
         inspection_counts.reverse();
 
-        inspection_counts[0] * inspection_counts[1]
+        let count = |c: usize| N::from_i64(c as i64);
+        count(inspection_counts[0]).mul(&count(inspection_counts[1]))
     }
 }
 
-pub fn day_11() -> (String, String) {
-    let f = read_to_string("input/day11.txt").unwrap();
-
-    let lines: Vec<&str> = f.lines().filter(|l| !l.is_empty()).collect();
+fn solve<N: Integer>(input: &str) -> NResult<(N, N)> {
+    let lines: Vec<&str> = input.lines().filter(|l| !l.is_empty()).collect();
 
     let mut monkeys = vec![];
-    let mut co_prime = 1;
+    let mut co_prime = N::from_i64(1);
 
     let mut items = vec![];
 
     for (m, x) in lines.chunks(6).enumerate() {
         let parse = |s| str::parse::<usize>(s).unwrap();
+        let parse_n = |s: &str| s.parse::<N>().ok().unwrap();
 
         let (_, items_str) = x[1].split_at("  Starting Items: ".len());
 
         for i in items_str.split(", ") {
             items.push(Item {
                 owner: m,
-                worry: parse(i),
+                worry: parse_n(i),
             });
         }
 
         let (_, op_str) = x[2].split_at("  Operation: new = ".len());
 
-        let op: MonkeyOp = if op_str == "old * old" {
+        let op = if op_str == "old * old" {
             MonkeyOp::Square
         } else {
             let (_, o_str) = op_str.split_at("old _ ".len());
-            let o = parse(o_str);
+            let o = parse_n(o_str);
             if op_str.starts_with("old + ") {
                 MonkeyOp::Add(o)
             } else {
@@ -119,41 +132,53 @@ pub fn day_11() -> (String, String) {
         let (_, route_a) = x[4].split_at("    If true: throw to monkey ".len());
         let (_, route_b) = x[5].split_at("    If false: throw to monkey ".len());
 
-        let route = [parse(test_str), parse(route_a), parse(route_b)];
-
-        co_prime *= route[0];
+        let test = parse_n(test_str);
+        co_prime = co_prime.mul(&test)?;
         monkeys.push(Monkey {
-            route,
+            test,
+            route: [parse(route_a), parse(route_b)],
             num: m,
             op,
             inspected_count: 0,
         });
     }
 
+    let limits = Limits {
+        divisor: None,
+        co_prime,
+        reduce_above: N::from_i64(1_000_000_000),
+    };
+
     let mut troop_1 = MonkeyTroop {
         monkeys: monkeys.clone(),
         items: items.clone(),
-        co_prime,
-        divisor: 1,
+        limits: limits.clone(),
     };
 
     let mut troop_3 = MonkeyTroop {
         monkeys,
         items,
-        co_prime,
-        divisor: 3,
+        limits: Limits {
+            divisor: Some(N::from_i64(3)),
+            ..limits
+        },
     };
 
     for _ in 0..20 {
-        troop_3.run();
+        troop_3.run()?;
     }
 
     for _ in 0..10_000 {
-        troop_1.run();
+        troop_1.run()?;
     }
 
-    (
-        format!("{}", troop_3.monkey_business()),
-        format!("{}", troop_1.monkey_business()),
-    )
+    Ok((troop_3.monkey_business()?, troop_1.monkey_business()?))
+}
+
+pub fn day_11() -> (String, String) {
+    let f = read_to_string("input/day11.txt").unwrap();
+
+    let (a, b) = solve::<Int>(&f).unwrap_or_else(|e| panic!("day 11: {e}"));
+
+    (format!("{a}"), format!("{b}"))
 }
@@ -1,7 +1,9 @@
+use crate::utils::geom::{Dir8, Point2};
+use crate::utils::grid::Grid;
+use crate::utils::viz::{Frame, Visualize, BLACK, GREY, YELLOW};
+use std::fmt::{Display, Formatter};
 use std::fs::read_to_string;
 
-type Pos = (isize, isize);
-
 const MAP_HEIGHT: usize = 200;
 const MAP_WIDTH: usize = 400;
 const MAP_OFFSET: usize = 300;
@@ -13,45 +15,53 @@ enum Square {
     Sand,
 }
 
+impl Display for Square {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let c = match self {
+            Square::Rock => '#',
+            Square::Air => ' ',
+            Square::Sand => 'o',
+        };
+        write!(f, "{c}")
+    }
+}
+
 #[derive(Clone)]
-struct Grid {
-    cells: Vec<Vec<Square>>,
+struct Cave {
+    cells: Grid<Square>,
     lowest_y: isize,
-    sand_count: usize,
+    // In the order it came to rest, so filling the cave can be replayed
+    settled: Vec<Point2>,
 }
 
-impl Grid {
-    fn get(&mut self, pos: Pos) -> &mut Square {
-        let (px, py) = pos;
-        &mut self.cells[py as usize][px as usize - MAP_OFFSET]
+impl Cave {
+    fn get(&mut self, pos: Point2) -> &mut Square {
+        &mut self.cells[(pos.x as usize - MAP_OFFSET, pos.y as usize)]
     }
 
-    fn paint(&mut self, from: Pos, to: Pos) {
-        let (from_x, from_y) = from;
-        let (to_x, to_y) = to;
+    // Paints a horizontal or vertical line of rock, including both ends
+    fn paint(&mut self, from: Point2, to: Point2) {
+        let step = (to - from).signum();
+        let mut p = from;
 
-        if from_x == to_x {
-            for y in from_y.min(to_y)..=from_y.max(to_y) {
-                self.lowest_y = self.lowest_y.max(y);
-                *self.get((from_x, y)) = Square::Rock;
-            }
-        } else {
-            for x in from_x.min(to_x)..=from_x.max(to_x) {
-                self.lowest_y = self.lowest_y.max(from_y);
-                *self.get((x, from_y)) = Square::Rock;
+        loop {
+            self.lowest_y = self.lowest_y.max(p.y);
+            *self.get(p) = Square::Rock;
+
+            if p == to {
+                break;
             }
+            p += step;
         }
     }
 
-    fn spawn_sand(&mut self, pos: Pos) -> bool {
-        let (px, py) = pos;
-
-        if py >= (MAP_HEIGHT - 1) as isize {
+    fn spawn_sand(&mut self, pos: Point2) -> bool {
+        if pos.y >= (MAP_HEIGHT - 1) as isize {
             return true;
         }
 
-        for x in [0, -1, 1] {
-            let p = (px + x, py + 1);
+        for dir in [Dir8::South, Dir8::SouthWest, Dir8::SouthEast] {
+            let p = pos + dir;
             let g = self.get(p);
 
             if *g == Square::Air && self.spawn_sand(p) {
@@ -60,76 +70,99 @@ impl Grid {
         }
 
         *self.get(pos) = Square::Sand;
-        self.sand_count += 1;
+        self.settled.push(pos);
 
         false
     }
 
     #[allow(unused)]
-    fn show(&mut self) {
-        for y in 0..self.cells.len() {
-            println!();
-
-            for x in 0..self.cells[y].len() {
-                print!(
-                    "{}",
-                    if self.cells[y][x] == Square::Air {
-                        " "
-                    } else {
-                        "R"
-                    }
+    fn show(&self) {
+        print!("{}", self.cells);
+    }
+
+    fn read(input: &str) -> Cave {
+        let mut grid = Cave {
+            cells: Grid::new(MAP_WIDTH, MAP_HEIGHT, Square::Air),
+            lowest_y: 0,
+            settled: vec![],
+        };
+
+        for l in input.lines() {
+            let locations = l.split(" -> ");
+
+            let mut last = None;
+            for loc in locations {
+                let (xs, ys) = loc.split_once(',').unwrap();
+                let p = Point2::new(
+                    str::parse::<isize>(xs).unwrap(),
+                    str::parse::<isize>(ys).unwrap(),
                 );
+
+                if let Some(old) = last {
+                    grid.paint(old, p);
+                }
+
+                last = Some(p);
             }
         }
-    }
-}
 
-pub fn day_14() -> (String, String) {
-    let f = read_to_string("input/day14.txt").unwrap();
-
-    let mut out = vec![];
-    for _ in 0..MAP_HEIGHT {
-        out.push(vec![Square::Air; MAP_WIDTH]);
+        grid
     }
 
-    let mut grid = Grid {
-        cells: out,
-        lowest_y: 0,
-        sand_count: 0,
-    };
+    fn with_floor(&self) -> Cave {
+        let mut cave = self.clone();
+        let floor = cave.lowest_y + 2;
+        cave.paint(
+            Point2::new(MAP_OFFSET as isize, floor),
+            Point2::new((MAP_OFFSET + MAP_WIDTH - 1) as isize, floor),
+        );
+        cave
+    }
+}
 
-    for l in f.lines() {
-        let locations = l.split(" -> ");
+impl Visualize for Cave {
+    fn frame(&self) -> Frame {
+        let mut frame = Frame::new(MAP_WIDTH, MAP_HEIGHT, BLACK);
+        for ((x, y), square) in self.cells.iter() {
+            match square {
+                Square::Rock => frame.set(x, y, GREY),
+                Square::Sand => frame.set(x, y, YELLOW),
+                Square::Air => {}
+            }
+        }
+        frame
+    }
+}
 
-        let mut last = None;
-        for loc in locations {
-            let (xs, ys) = loc.split_once(',').unwrap();
-            let x = str::parse::<isize>(xs).unwrap();
-            let y = str::parse::<isize>(ys).unwrap();
+// Replays part two, with a frame for every hundred grains of sand
+pub fn visualize(frames: &mut dyn FnMut(&dyn Visualize)) {
+    let f = read_to_string("input/day14.txt").unwrap();
+    let empty = Cave::read(&f).with_floor();
 
-            if let Some((old_x, old_y)) = last {
-                grid.paint((old_x, old_y), (x, y));
-            }
+    let mut filled = empty.clone();
+    filled.spawn_sand(Point2::new(500, 0));
 
-            last = Some((x, y));
+    let mut cave = empty;
+    frames(&cave);
+    for grains in filled.settled.chunks(100) {
+        for p in grains {
+            *cave.get(*p) = Square::Sand;
         }
+        frames(&cave);
     }
+}
 
-    let mut grid_floor = grid.clone();
+pub fn day_14() -> (String, String) {
+    let f = read_to_string("input/day14.txt").unwrap();
 
-    grid_floor.paint(
-        (MAP_OFFSET as isize, grid_floor.lowest_y + 2),
-        (
-            (MAP_OFFSET + MAP_WIDTH - 1) as isize,
-            grid_floor.lowest_y + 2,
-        ),
-    );
+    let mut grid = Cave::read(&f);
+    let mut grid_floor = grid.with_floor();
 
-    grid_floor.spawn_sand((500, 0));
-    grid.spawn_sand((500, 0));
+    grid_floor.spawn_sand(Point2::new(500, 0));
+    grid.spawn_sand(Point2::new(500, 0));
 
     (
-        format!("{}", grid.sand_count),
-        format!("{}", grid_floor.sand_count),
+        format!("{}", grid.settled.len()),
+        format!("{}", grid_floor.settled.len()),
     )
 }
@@ -1,39 +1,34 @@
-use regex::Regex;
+use crate::utils::geom::Point2;
+use crate::utils::interval::{Interval, IntervalSet};
+use crate::utils::parse::{int, lines, parse_all, template, Parser};
+use std::collections::HashSet;
 use std::fs::read_to_string;
 
-type Pos = (isize, isize);
-type Range = (isize, isize);
-
-type Square = (Pos, Pos);
+type Square = (Point2, Point2);
 
 struct Sensor {
-    pos: Pos,
-    beacon: Pos,
+    pos: Point2,
+    beacon: Point2,
 }
 
 impl Sensor {
-    fn exclusion_line(&self, line_y: isize) -> Option<Range> {
-        let (x, y) = self.pos;
-        let (xb, yb) = self.beacon;
-        let dist = (x - xb).abs() + (y - yb).abs();
-
-        let ydist = (y - line_y).abs();
+    fn range(&self) -> isize {
+        self.pos.manhattan(self.beacon)
+    }
 
-        let diff = dist - ydist;
+    // Empty if the line is out of range
+    fn exclusion_line(&self, line_y: isize) -> Interval<isize> {
+        let Point2 { x, y } = self.pos;
+        let diff = self.range() - (y - line_y).abs();
 
-        if diff < 0 {
-            None
-        } else {
-            Some((x - diff, x + diff))
-        }
+        Interval::inclusive(x - diff, x + diff)
     }
 
     // The manhattan distance of each sensor is made up of four line segments, with dy/dx +/- 1
     // This projects them back to the Y axis
     fn y_intercepts(&self) -> ((isize, isize), (isize, isize)) {
-        let (x, y) = self.pos;
-        let (xb, yb) = self.beacon;
-        let dist = (x - xb).abs() + (y - yb).abs();
+        let Point2 { x, y } = self.pos;
+        let dist = self.range();
 
         (
             // Down
@@ -43,84 +38,48 @@ impl Sensor {
         )
     }
 
-    fn excludes_pos(&self, p: Pos) -> bool {
-        let calc_dist = |a: Pos, b: Pos| {
-            let (x1, y1) = a;
-            let (x2, y2) = b;
-            (x1 - x2).abs() + (y1 - y2).abs()
-        };
-
-        calc_dist(self.beacon, self.pos) >= calc_dist(self.pos, p)
-    }
-}
-
-pub fn merge_ranges(ranges: &[Range]) -> Vec<Range> {
-    let mut ranges = Vec::from(ranges);
-    ranges.sort_by(|(al, _), (bl, _)| al.cmp(bl));
-
-    let mut out = vec![];
-    let mut last: Option<Range> = None;
-
-    for (el, eh) in ranges {
-        if let Some((ll, lh)) = last {
-            if el <= lh {
-                last = Some((ll.min(el), lh.max(eh)))
-            } else {
-                out.push((ll, lh));
-                last = Some((el, eh))
-            }
-        } else {
-            last = Some((el, eh))
-        }
-    }
-
-    if let Some(l) = last {
-        out.push(l);
+    fn excludes_pos(&self, p: Point2) -> bool {
+        self.range() >= self.pos.manhattan(p)
     }
-
-    out
 }
 
 // Recursive method with early out, but it was too slow (~40ms vs ~1ms)
 #[allow(unused)]
-fn check_square(sensors: &[Sensor], square: Square) -> Option<Pos> {
-    let ((x1, y1), (x2, y2)) = square;
+fn check_square(sensors: &[Sensor], square: Square) -> Option<Point2> {
+    let (Point2 { x: x1, y: y1 }, Point2 { x: x2, y: y2 }) = square;
 
     for s in sensors {
-        if s.excludes_pos((x1, y1))
-            && s.excludes_pos((x2, y1))
-            && s.excludes_pos((x1, y2))
-            && s.excludes_pos((x2, y2))
-        {
+        let corners = [(x1, y1), (x2, y1), (x1, y2), (x2, y2)];
+        if corners.iter().all(|&c| s.excludes_pos(c.into())) {
             return None;
         }
     }
 
     if x1 == x2 && y1 == y2 {
-        return Some((x1, y1));
+        return Some(Point2::new(x1, y1));
     }
 
     let mx = (x1 + x2 + 1) / 2;
     let my = (y1 + y2 + 1) / 2;
 
-    if let Some(p) = check_square(sensors, ((mx, my), (x2, y2))) {
+    if let Some(p) = check_square(sensors, (Point2::new(mx, my), square.1)) {
         return Some(p);
     }
 
     if mx != x1 && my != y1 {
-        if let Some(p) = check_square(sensors, ((x1, y1), (mx - 1, my - 1))) {
+        if let Some(p) = check_square(sensors, (square.0, Point2::new(mx - 1, my - 1))) {
             return Some(p);
         }
     }
 
     if mx != x1 {
-        if let Some(p) = check_square(sensors, ((x1, my), (mx - 1, y2))) {
+        if let Some(p) = check_square(sensors, (Point2::new(x1, my), Point2::new(mx - 1, y2))) {
             return Some(p);
         }
     }
 
     if my != y1 {
-        if let Some(p) = check_square(sensors, ((mx, y1), (x2, my - 1))) {
+        if let Some(p) = check_square(sensors, (Point2::new(mx, y1), Point2::new(x2, my - 1))) {
             return Some(p);
         }
     }
@@ -129,26 +88,23 @@ fn check_square(sensors: &[Sensor], square: Square) -> Option<Pos> {
 }
 
 // Each sensor forms an exclusion range for a given Y value
-// This merges these exclusion ranges to estimate total coverage
+// This merges these exclusion ranges to estimate total coverage. Beacons that are on the line
+// are excluded too, but they're obviously places a beacon can be, so they're taken back out.
 fn part_1(sensors: &[Sensor]) -> isize {
-    let mut exclusions = vec![];
+    const LINE_Y: isize = 2000000;
 
-    for s in sensors {
-        if let Some(e) = s.exclusion_line(2000000) {
-            exclusions.push(e);
-        }
-    }
-
-    exclusions = merge_ranges(&exclusions);
+    let exclusions = sensors
+        .iter()
+        .map(|s| s.exclusion_line(LINE_Y))
+        .collect::<IntervalSet<isize>>();
 
-    let mut out = 0;
+    let beacons = sensors
+        .iter()
+        .map(|s| s.beacon)
+        .filter(|b| b.y == LINE_Y && exclusions.contains(b.x))
+        .collect::<HashSet<Point2>>();
 
-    for (l, h) in exclusions {
-        // I'm pretty sure it _should_ be + 1!?
-        out += h - l; //+ 1;
-    }
-
-    out
+    exclusions.len() - beacons.len() as isize
 }
 
 fn part_2(sensors: &[Sensor]) -> isize {
@@ -177,7 +133,7 @@ fn part_2(sensors: &[Sensor]) -> isize {
                     if (0..=4_000_000).contains(&chx) && (0..=4_000_000).contains(&chy) {
                         let mut blocked = false;
                         for s in sensors {
-                            if s.excludes_pos((chx, chy)) {
+                            if s.excludes_pos(Point2::new(chx, chy)) {
                                 blocked = true;
                             }
                         }
@@ -195,21 +151,19 @@ fn part_2(sensors: &[Sensor]) -> isize {
 pub fn day_15() -> (String, String) {
     let f = read_to_string("input/day15.txt").unwrap();
 
-    let re =
-        Regex::new(r"^Sensor at x=(-?\d+), y=(-?\d+): closest beacon is at x=(-?\d+), y=(-?\d+)$")
-            .unwrap();
-
-    let mut sensors = vec![];
-
-    for l in f.lines() {
-        let c = re.captures(l).unwrap();
-        let parse = |i| str::parse::<isize>(&c[i]).unwrap();
-
-        let pos = (parse(1), parse(2));
-        let beacon = (parse(3), parse(4));
-
-        sensors.push(Sensor { pos, beacon });
-    }
+    let parts = &[
+        "Sensor at x=",
+        ", y=",
+        ": closest beacon is at x=",
+        ", y=",
+        "",
+    ];
+    let sensor = template(parts, int()).map(|v| Sensor {
+        pos: Point2::new(v[0], v[1]),
+        beacon: Point2::new(v[2], v[3]),
+    });
+
+    let sensors = parse_all(lines(sensor), &f).unwrap();
 
     (
         format!("{}", part_1(&sensors)),
@@ -1,6 +1,7 @@
+use crate::utils::branch_bound::{BranchAndBound, Solver};
+use crate::utils::parse::{alpha, alt, lines, lit, pair, parse_all, preceded, sep_by, uint};
+use crate::utils::search::{bfs_in, Dense};
 use itertools::Itertools;
-use regex::Regex;
-use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs::read_to_string;
 
@@ -22,25 +23,13 @@ impl System {
     // Precached pathfind of the shortest path from any valve to any other valve
     fn pathfind(&mut self) {
         for i in 0..self.valves.len() {
-            let mut times: Vec<Option<usize>> = vec![None; self.valves.len()];
-            fn pf(s: &System, times: &mut Vec<Option<usize>>, p: usize, d: usize) {
-                if let Some(t) = times[p] {
-                    if t > d {
-                        times[p] = None;
-                    }
-                }
-
-                if times[p].is_none() {
-                    times[p] = Some(d);
-
-                    for x in &s.valves[p].connections {
-                        pf(s, times, *x, d + 1);
-                    }
-                }
-            }
-            pf(self, &mut times, self.valves[i].num, 0);
+            let connections = |v: usize| self.valves[v].connections.iter().copied();
+            let costs = Dense::new(self.valves.len(), |v: usize| v);
+            let paths = bfs_in(self.valves[i].num, connections, |_| false, costs, ());
 
-            self.valves[i].times = times.iter().map(|n| n.unwrap()).collect();
+            self.valves[i].times = (0..self.valves.len())
+                .map(|v| paths.cost(v).unwrap())
+                .collect();
         }
     }
 
@@ -63,7 +52,7 @@ impl System {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
 struct Entity {
     pos: u8,
     dest: Option<(u8, u8)>,
@@ -79,7 +68,7 @@ impl Entity {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
 struct SearchState {
     player: Entity,
     elephant: Entity,
@@ -90,30 +79,6 @@ struct SearchState {
     reward: usize,
 }
 
-// We search depth first, but sort candidates of equal day to find the most promising
-// The most fruitful elimination of nodes in the tree appears to by culling intermediate states
-// based on their best possible outcome compared to the known best solution.
-// Intermediate states contain a lot of data - 2x positions, valves and current volume. This puts
-// dynamic programming off the table, and makes comparing intermediate states with each other
-// for culling difficult considering we only end up visiting about 400k of them.
-impl PartialOrd for SearchState {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(
-            self.step
-                .cmp(&other.step)
-                .then(self.reward.cmp(&other.reward)),
-        )
-    }
-}
-
-impl Ord for SearchState {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.step
-            .cmp(&other.step)
-            .then(self.reward.cmp(&other.reward))
-    }
-}
-
 impl SearchState {
     fn open_valve(&mut self, search: &Search, v: u8) {
         debug_assert_eq!((self.enabled >> v) & 1, 0);
@@ -351,11 +316,7 @@ impl SearchState {
 struct Search {
     steps: usize,
     elephant: bool,
-
-    checked: usize,
-    skipped: usize,
     system: System,
-    best: Option<SearchState>,
     initial: SearchState,
 }
 
@@ -364,10 +325,7 @@ impl Search {
         Search {
             steps,
             elephant,
-            checked: 0,
-            skipped: 0,
             system,
-            best: None,
             initial: SearchState {
                 player: Entity {
                     pos: start,
@@ -386,46 +344,57 @@ impl Search {
         }
     }
 
-    // Searches recursively through states, sorting by expected value at each node
-    // I found that, when using a priority queue, the best way to tune it was a depth
-    // first search anyway. This replicates the logic with lower allocation since more
-    // lives on the stack.
-    fn search_inner(&mut self, state: SearchState) {
-        let choices = state.next_states(self);
-        self.checked += 1;
-
-        for child in choices.into_iter().sorted().rev() {
-            if let Some(best) = self.best {
-                let best_outcome = child.best_possible_outcome(self);
-                if best.volume >= best_outcome as u32 {
-                    self.skipped += 1;
-                    continue;
-                }
+    fn search(&self) -> u32 {
+        Solver::new(self).solve(self.initial).volume
+    }
+}
 
-                if best.volume < child.volume {
-                    self.best = Some(child);
-                }
-            } else {
-                self.best = Some(child);
-            }
+// We search depth first, but sort candidates of equal day to find the most promising
+// The most fruitful elimination of nodes in the tree appears to by culling intermediate states
+// based on their best possible outcome compared to the known best solution.
+// Intermediate states contain a lot of data - 2x positions, valves and current volume. This puts
+// dynamic programming off the table, and makes comparing intermediate states with each other
+// for culling difficult considering we only end up visiting about 400k of them.
+impl BranchAndBound for Search {
+    type Node = SearchState;
+    type Value = u32;
+    type Key = (u8, usize);
 
-            self.search_inner(child);
-        }
+    fn successors(&self, state: &SearchState) -> Vec<SearchState> {
+        state.next_states(self)
+    }
+
+    fn value(&self, state: &SearchState) -> u32 {
+        state.volume
     }
 
-    fn search(&mut self) -> u32 {
-        let initial = self.initial;
+    fn upper_bound(&self, state: &SearchState) -> u32 {
+        state.best_possible_outcome(self) as u32
+    }
 
-        self.search_inner(initial);
-        self.best.unwrap().volume
+    fn key(&self, state: &SearchState) -> (u8, usize) {
+        (state.step, state.reward)
+    }
+
+    fn depth(&self, state: &SearchState) -> usize {
+        state.step as usize
     }
 }
 
 pub fn day_16() -> (String, String) {
     let f = read_to_string("input/day16.txt").unwrap();
 
-    let re =
-        Regex::new(r"^Valve (..) has flow rate=(\d+); tunnels* leads* to valves* (.+)$").unwrap();
+    let tunnels = alt(
+        lit("; tunnels lead to valves "),
+        lit("; tunnel leads to valve "),
+    );
+    let line = pair(
+        pair(
+            preceded(lit("Valve "), alpha()),
+            preceded(lit(" has flow rate="), uint::<usize>()),
+        ),
+        preceded(tunnels, sep_by(alpha(), lit(", "))),
+    );
 
     let mut mapping = HashMap::new();
 
@@ -440,14 +409,11 @@ pub fn day_16() -> (String, String) {
     };
 
     let mut valves = vec![];
-    for l in f.lines() {
-        let c = re.captures(l).unwrap();
-
-        let valve = get_mapping(&c[1]);
-        let flow = str::parse::<usize>(&c[2]).unwrap();
-        let to = c[3]
-            .split(", ")
-            .map(&mut get_mapping)
+    for ((name, flow), tunnels) in parse_all(lines(line), &f).unwrap() {
+        let valve = get_mapping(&name);
+        let to = tunnels
+            .iter()
+            .map(|t| get_mapping(t))
             .collect::<Vec<usize>>();
 
         valves.push(Valve {
@@ -458,7 +424,7 @@ pub fn day_16() -> (String, String) {
         });
     }
 
-    valves.sort_by(|va, vb| va.num.cmp(&vb.num));
+    valves.sort_by_key(|v| v.num);
 
     let mut s = System {
         valves,
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use crate::utils::cycle::find_hashed;
+use crate::utils::viz::{Frame, Visualize, BLACK, BLUE, GREY};
 use std::fs::read_to_string;
 
 #[derive(Clone)]
@@ -35,19 +36,34 @@ impl Control {
 }
 
 impl Field {
-    // Removes everything below a solid line, and stores how many rows were removed
+    // Removes every row that no falling piece could ever reach, keeping the one beneath the
+    // lowest reachable row for them to land on, and stores how many rows were removed. A cell is
+    // reachable if it can be got to from above by moving down and sideways through empty cells.
     fn trim(&mut self) {
-        if self.rows.len() >= 5 {
-            for y in (0..self.rows.len()).rev() {
-                let r = self.rows[y];
-
-                if r == 0b1111111 {
-                    self.rows.drain(..y);
-                    self.trimmed += y;
-                    return;
+        const FULL: u64 = 0b1111111;
+        let mut reach = FULL;
+        let mut lowest = self.rows.len();
+
+        for y in (0..self.rows.len()).rev() {
+            let air = !self.rows[y] & FULL;
+            reach &= air;
+            loop {
+                let spread = (reach | reach << 1 | reach >> 1) & air;
+                if spread == reach {
+                    break;
                 }
+                reach = spread;
             }
+
+            if reach == 0 {
+                break;
+            }
+            lowest = y;
         }
+
+        let below = lowest.saturating_sub(1);
+        self.rows.drain(..below);
+        self.trimmed += below;
     }
 }
 
@@ -73,6 +89,10 @@ impl Piece {
     }
 
     fn collides_with_field(&self, field: &Field, (x, y): (isize, isize)) -> bool {
+        if x < 0 {
+            return true;
+        }
+
         for (py, l) in self.lines.iter().enumerate() {
             if ((l >> x) & 0b1111111).count_ones() != l.count_ones() {
                 return true;
@@ -101,6 +121,26 @@ impl Piece {
 }
 
 impl Game {
+    fn read(input: &str) -> Game {
+        let line = input.lines().next().unwrap();
+
+        let mut dirs = vec![];
+        for n in line.chars() {
+            if n == '<' {
+                dirs.push(-1);
+            } else {
+                dirs.push(1);
+            }
+        }
+
+        Game {
+            field: Field::default(),
+            control: Control { dirs, current: 0 },
+            piece_count: 0,
+            pieces: Piece::default_pieces(),
+        }
+    }
+
     #[allow(unused)]
     fn print(&self) {
         println!("Field {} {}", self.field.rows.len(), self.control.current);
@@ -123,42 +163,33 @@ impl Game {
         self.field.rows.len() + self.field.trimmed
     }
 
-    fn add_pieces_bulk(&mut self, num: usize) {
-        let mut hs = HashMap::new();
-
-        // Loops until it finds a cycle where the game is repeating. This happens if the
-        // trimmed field and instruction number are identical. Trimming a field removes everything
-        // above a complete line, as pieces below the complete line cannot impact a falling piece
-
-        loop {
-            for _ in 0..5 {
-                self.add_piece();
-            }
-            self.field.trim();
-
+    // Finds a cycle where the game is repeating. This happens if the trimmed field and
+    // instruction number are identical after some number of whole rounds of pieces. Trimming a
+    // field removes everything no falling piece can reach, as it can't affect where one lands.
+    // The height then grows by the same amount every time round. Pieces left over after the last
+    // whole round are dropped onto an earlier game in the same state, which grows by just as much.
+    fn height_after(&self, num: usize) -> usize {
+        let round = self.pieces.len();
+        let (rounds, extra) = (num / round, num % round);
+
+        let mut game = self.clone();
+        let cycle = find_hashed(
+            &mut game,
+            |g| {
+                g.add_pieces(round);
+                g.field.trim();
+            },
             // The clone doesn't seem intrinsically necessary. But it's running in ~1ms, so ¯\_(ツ)_/¯
-            let existing = hs.get(&(self.field.rows.clone(), self.control.current));
-
-            if let Some((tiles, piece_count)) = existing {
-                let diff = self.piece_count - piece_count;
-                let trimmed_diff = self.field.trimmed - tiles;
+            |g| (g.field.rows.clone(), g.control.current),
+            |g| g.height() as isize,
+        );
 
-                let cycles = (num - self.piece_count) / diff;
+        let mut same = self.clone();
+        same.add_pieces(cycle.equivalent(rounds) * round);
+        let before = same.height();
+        same.add_pieces(extra);
 
-                self.piece_count += cycles * diff;
-                self.field.trimmed += cycles * trimmed_diff;
-                break;
-            } else {
-                hs.insert(
-                    (self.field.rows.clone(), self.control.current),
-                    (self.field.trimmed, self.piece_count),
-                );
-            }
-        }
-
-        while self.piece_count < num {
-            self.add_piece();
-        }
+        cycle.extrapolate(rounds) as usize + same.height() - before
     }
 
     fn add_pieces(&mut self, num: usize) {
@@ -190,33 +221,66 @@ impl Game {
     }
 }
 
-pub fn day_17() -> (String, String) {
-    let f = read_to_string("input/day17.txt").unwrap();
-    let line = f.lines().next().unwrap();
-
-    let mut dirs = vec![];
-    for n in line.chars() {
-        if n == '<' {
-            dirs.push(-1);
-        } else {
-            dirs.push(1);
+// The top of the tower, between the walls
+const VIEW_HEIGHT: usize = 64;
+
+impl Visualize for Game {
+    fn frame(&self) -> Frame {
+        let mut frame = Frame::new(9, VIEW_HEIGHT, BLACK);
+        let rows = &self.field.rows;
+
+        for y in 0..VIEW_HEIGHT {
+            frame.set(0, y, GREY);
+            frame.set(8, y, GREY);
+
+            let Some(r) = rows.len().checked_sub(y + 1).map(|i| rows[i]) else {
+                continue;
+            };
+            for n in 0..7 {
+                if (r << n) & (1 << 6) != 0 {
+                    frame.set(n + 1, y, BLUE);
+                }
+            }
         }
+        frame
     }
+}
 
-    let mut game_a = Game {
-        field: Field::default(),
-        control: Control { dirs, current: 0 },
-        piece_count: 0,
-        pieces: Piece::default_pieces(),
-    };
+// One frame per rock, for the 2022 rocks of part one
+pub fn visualize(frames: &mut dyn FnMut(&dyn Visualize)) {
+    let f = read_to_string("input/day17.txt").unwrap();
+    let mut game = Game::read(&f);
+
+    for _ in 0..2022 {
+        game.add_piece();
+        frames(&game);
+    }
+}
 
-    let mut game_b = game_a.clone();
+pub fn day_17() -> (String, String) {
+    let f = read_to_string("input/day17.txt").unwrap();
+    let mut game_a = Game::read(&f);
 
+    let part_b = game_a.height_after(1_000_000_000_000);
     game_a.add_pieces(2022);
-    game_b.add_pieces_bulk(1_000_000_000_000);
 
-    (
-        format!("{}", game_a.height()),
-        format!("{}", game_b.height()),
-    )
+    (format!("{}", game_a.height()), format!("{}", part_b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn height_after() {
+        let game = Game::read(">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>");
+
+        for num in [0, 3, 2022, 2023, 10_001] {
+            let mut simulated = game.clone();
+            simulated.add_pieces(num);
+            assert_eq!(game.height_after(num), simulated.height(), "{num} pieces");
+        }
+        assert_eq!(game.height_after(2022), 3068);
+        assert_eq!(game.height_after(1_000_000_000_000), 1514285714288);
+    }
 }
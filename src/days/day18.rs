@@ -1,123 +1,79 @@
+use crate::utils::bit_grid3::{BitGrid3, FACES};
+use crate::utils::geom::Point3;
 use std::fs::read_to_string;
 
-const OFFSETS: [(isize, isize, isize); 6] = [
-    (-1, 0, 0),
-    (1, 0, 0),
-    (0, 0, -1),
-    (0, 0, 1),
-    (0, -1, 0),
-    (0, 1, 0),
-];
+// Both parts are counted a face direction at a time. A cube's face in some direction is exposed
+// if the cell next to it in that direction is empty, and external if that cell can be reached by
+// flood filling from outside the droplet. The grid has a layer of padding on every side, so the
+// fill can get all the way around.
 
-#[derive(Copy, Clone)]
-struct Point {
-    solid: bool,
-    external: bool,
-}
+fn solve(input: &str) -> (usize, usize) {
+    let mut points = vec![];
 
-impl Point {
-    fn new() -> Point {
-        Point {
-            solid: false,
-            external: false,
-        }
+    for l in input.lines() {
+        let v = l.split(',').collect::<Vec<&str>>();
+        let parse = |i: usize| str::parse::<isize>(v[i]).unwrap();
+        points.push(Point3::new(parse(0), parse(1), parse(2)));
     }
-}
 
-struct Grid {
-    size: isize,
-    offset: isize,
-    fields: Vec<Point>,
-}
-
-impl Grid {
-    fn new(size: isize) -> Grid {
-        Grid {
-            fields: vec![Point::new(); (size * size * size) as usize],
-            offset: 2,
-            size,
-        }
+    // Moves the lowest coordinate on every axis to 1, leaving 0 as padding
+    let low = |axis: fn(&Point3) -> isize| points.iter().map(axis).min().unwrap() - 1;
+    let offset = Point3::new(low(|p| p.x), low(|p| p.y), low(|p| p.z));
+    for p in &mut points {
+        *p -= offset;
     }
 
-    fn get(&mut self, point: (isize, isize, isize)) -> Option<&mut Point> {
-        if point.0 + self.offset < 0
-            || point.0 >= self.size - self.offset
-            || point.1 + self.offset < 0
-            || point.1 >= self.size - self.offset
-            || point.2 + self.offset < 0
-            || point.2 >= self.size - self.offset
-        {
-            return None;
-        }
+    let size = points.iter().map(|p| p.x.max(p.y).max(p.z)).max().unwrap() as usize + 2;
 
-        Some(
-            &mut self.fields[((point.0 + self.offset) * self.size * self.size
-                + (point.1 + self.offset) * self.size
-                + (point.2 + self.offset)) as usize],
-        )
+    let mut solid = BitGrid3::new(size, size, size);
+    for p in &points {
+        solid.set(p.x as usize, p.y as usize, p.z as usize, true);
     }
-}
 
-fn flood_fill_external(grid: &mut Grid, p: (isize, isize, isize)) {
-    let mut points = vec![p];
+    let mut outside = BitGrid3::new(size, size, size);
+    outside.set(0, 0, 0, true);
+    let outside = outside.flood_fill(&solid);
 
-    grid.get(p).unwrap().external = true;
+    let mut surface = 0;
+    let mut surface_external = 0;
 
-    loop {
-        let mut next = vec![];
-        for p in points {
-            for (ox, oy, oz) in &OFFSETS {
-                let np = (ox + p.0, oy + p.1, oz + p.2);
-                if let Some(cube) = grid.get(np) {
-                    if !(cube.solid || cube.external) {
-                        cube.external = true;
-                        next.push(np)
-                    }
-                }
-            }
-        }
+    for (ox, oy, oz) in FACES {
+        let mut exposed = solid.clone();
+        exposed.apply(&solid.shifted(-ox, -oy, -oz), |a, b| a & !b);
+        surface += exposed.count();
 
-        if next.is_empty() {
-            return;
-        }
-        points = next;
+        let mut external = solid.clone();
+        external.apply(&outside.shifted(-ox, -oy, -oz), |a, b| a & b);
+        surface_external += external.count();
     }
+
+    (surface, surface_external)
 }
 
 pub fn day_18() -> (String, String) {
     let f = read_to_string("input/day18.txt").unwrap();
 
-    let mut points = vec![];
-
-    for l in f.lines() {
-        let v = l.split(',').collect::<Vec<&str>>();
-        let parse = |i| str::parse::<isize>(*v.get(i).unwrap()).unwrap();
-        let point = (parse(0), parse(1), parse(2));
-        points.push(point)
-    }
-
-    let mut grid = Grid::new(26);
-
-    for p in &points {
-        grid.get(*p).unwrap().solid = true;
-    }
-
-    flood_fill_external(&mut grid, (0, 0, 0));
-
-    let mut surface = 0;
-    let mut surface_external = 0;
+    let (surface, surface_external) = solve(&f);
+    (format!("{surface}"), format!("{surface_external}"))
+}
 
-    for (px, py, pz) in &points {
-        for (ox, oy, oz) in &OFFSETS {
-            let cube = grid.get((px + ox, py + oy, pz + oz)).unwrap();
-            if !cube.solid {
-                surface += 1;
-                if cube.external {
-                    surface_external += 1;
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn negative_coordinates() {
+        // A hollow 3x3x3 cube, well below zero
+        let mut input = String::new();
+        for z in -9..-6 {
+            for y in -5..-2 {
+                for x in -3..0 {
+                    if (x, y, z) != (-2, -4, -8) {
+                        input += &format!("{x},{y},{z}\n");
+                    }
                 }
             }
         }
+        assert_eq!(solve(&input), (54 + 6, 54));
     }
-
-    (format!("{surface}"), format!("{surface_external}"))
 }
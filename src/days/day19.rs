@@ -1,8 +1,9 @@
-use regex::Regex;
+use crate::utils::branch_bound::{BranchAndBound, Solver};
+use crate::utils::parse::{lines, parse_all, template, uint};
 use std::fs::read_to_string;
 use std::ops::{Add, Sub};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
 struct Value {
     values: [usize; 4],
 }
@@ -12,8 +13,8 @@ impl Add for Value {
 
     fn add(self, other: Self) -> Self {
         let mut values = [0; 4];
-        for n in 0..values.len() {
-            values[n] = self.values[n] + other.values[n];
+        for (n, v) in values.iter_mut().enumerate() {
+            *v = self.values[n] + other.values[n];
         }
         Self { values }
     }
@@ -24,8 +25,8 @@ impl Sub for Value {
 
     fn sub(self, other: Self) -> Self {
         let mut values = [0; 4];
-        for n in 0..values.len() {
-            values[n] = self.values[n] - other.values[n];
+        for (n, v) in values.iter_mut().enumerate() {
+            *v = self.values[n] - other.values[n];
         }
         Self { values }
     }
@@ -52,7 +53,7 @@ struct Blueprint {
     prices: [Value; 4],
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
 struct State {
     step: usize,
     // This is a bit of a type error...
@@ -61,7 +62,7 @@ struct State {
 }
 
 impl State {
-    fn value(&self) -> usize {
+    fn value(self) -> usize {
         self.inventory.values[3]
     }
 
@@ -70,18 +71,18 @@ impl State {
     // build option a separate inventory, so the choice to build A never interferes with B,
     // but both are constrained individually by starting resources.
     fn best_outcome(&self, search: &Search) -> usize {
-        let mut clone = self.clone();
+        let mut clone = *self;
 
         let mut split_inv = [clone.inventory; 4];
 
         for _ in self.step..search.depth {
             let mut new_robots = clone.robots;
-            for c in 0..4 {
-                if split_inv[c].each_greater_equal(&search.blueprint.prices[c]) {
-                    split_inv[c] = split_inv[c] - search.blueprint.prices[c];
+            for (c, inv) in split_inv.iter_mut().enumerate() {
+                if inv.each_greater_equal(&search.blueprint.prices[c]) {
+                    *inv = *inv - search.blueprint.prices[c];
                     new_robots.values[c] += 1;
                 }
-                split_inv[c] = split_inv[c] + clone.robots;
+                *inv = *inv + clone.robots;
             }
 
             clone.inventory = clone.inventory + clone.robots;
@@ -103,13 +104,11 @@ impl State {
         // This picks a build option, and waits for enough resources to be generated
         for n in 0..4 {
             // Don't try to build something in input materials aren't being produced
-            if n == 2 || n == 3 {
-                if self.robots.values[n - 1] == 0 {
-                    continue;
-                }
+            if (n == 2 || n == 3) && self.robots.values[n - 1] == 0 {
+                continue;
             }
 
-            let mut clone = self.clone();
+            let mut clone = *self;
 
             // Wait until sufficient resources exist
             loop {
@@ -131,8 +130,8 @@ impl State {
 
         // If no build options are available (sufficient resources won't be generated by the end
         // of the search period), generate a single choice that waits until the end.
-        if out.len() == 0 {
-            let mut clone = self.clone();
+        if out.is_empty() {
+            let mut clone = *self;
             while clone.step < search.depth {
                 clone.step += 1;
                 clone.inventory = clone.inventory + clone.robots;
@@ -151,74 +150,64 @@ struct Search {
     initial_state: State,
 }
 
-struct SearchScratch {
-    best: Option<State>,
-    iters: usize,
-}
-
-impl Search {
-    // Performs a depth first search, using best possible outcome above both to prune paths and
-    // prioritize search order.
-    fn search_inner(&self, scratch: &mut SearchScratch, state: State) {
-        let mut succ = state.successors(&self);
-
-        scratch.iters += 1;
-        succ.sort_by(|a, b| b.best_outcome(&self).cmp(&a.best_outcome(&self)));
-
-        for v in succ {
-            if let Some(b) = scratch.best {
-                if b.value() >= v.best_outcome(&self) {
-                    continue;
-                }
-            }
+// Performs a depth first search, using best possible outcome above both to prune paths and
+// prioritize search order. Memoizing doesn't help, since states rarely repeat, and iterative
+// deepening is a disaster - shallow passes find poor incumbents that barely prune anything.
+impl BranchAndBound for Search {
+    type Node = State;
+    type Value = usize;
+    type Key = usize;
 
-            if let Some(b) = scratch.best {
-                if b.value() < v.value() {
-                    scratch.best = None;
-                }
-            };
+    fn successors(&self, state: &State) -> Vec<State> {
+        state.successors(self)
+    }
 
-            if scratch.best.is_none() {
-                scratch.best = Some(v);
-            }
+    fn value(&self, state: &State) -> usize {
+        state.value()
+    }
 
-            if v.step < self.depth {
-                self.search_inner(scratch, v);
-            }
-        }
+    fn upper_bound(&self, state: &State) -> usize {
+        state.best_outcome(self)
     }
 
-    fn search(&self) -> usize {
-        let mut scratch = SearchScratch {
-            best: None,
-            iters: 0,
-        };
+    fn key(&self, state: &State) -> usize {
+        state.best_outcome(self)
+    }
 
-        self.search_inner(&mut scratch, self.initial_state);
+    fn depth(&self, state: &State) -> usize {
+        state.step
+    }
+}
 
-        scratch.best.unwrap().value()
+impl Search {
+    fn search(self) -> usize {
+        Solver::new(&self).solve(self.initial_state).value()
     }
 }
 
 pub fn day_19() -> (String, String) {
     let f = read_to_string("input/day19.txt").unwrap();
-    let re = Regex::new(
-        r"^Blueprint (\d+): Each ore robot costs (\d+) ore\. Each clay robot costs (\d+) ore\. Each obsidian robot costs (\d+) ore and (\d+) clay\. Each geode robot costs (\d+) ore and (\d+) obsidian\.$",
-    ).unwrap();
+    let parts = &[
+        "Blueprint ",
+        ": Each ore robot costs ",
+        " ore. Each clay robot costs ",
+        " ore. Each obsidian robot costs ",
+        " ore and ",
+        " clay. Each geode robot costs ",
+        " ore and ",
+        " obsidian.",
+    ];
 
     let mut blueprints = vec![];
 
-    for l in f.lines() {
-        let c = re.captures(l).unwrap();
-        let v = |i: usize| str::parse::<usize>(&c[i + 1]).unwrap();
-
+    for v in parse_all(lines(template(parts, uint::<usize>())), &f).unwrap() {
         blueprints.push(Blueprint {
-            id: v(0),
+            id: v[0],
             prices: [
-                Value::new([v(1), 0, 0, 0]),
-                Value::new([v(2), 0, 0, 0]),
-                Value::new([v(3), v(4), 0, 0]),
-                Value::new([v(5), 0, v(6), 0]),
+                Value::new([v[1], 0, 0, 0]),
+                Value::new([v[2], 0, 0, 0]),
+                Value::new([v[3], v[4], 0, 0]),
+                Value::new([v[5], 0, v[6], 0]),
             ],
         });
     }
@@ -244,10 +233,10 @@ pub fn day_19() -> (String, String) {
 
     // Part B: First three blueprints searched, multiply result into accumulator
     let mut part_b = 1;
-    for n in 0..3 {
+    for b in blueprints.iter().take(3) {
         let search = Search {
             depth: 32,
-            blueprint: blueprints[n].clone(),
+            blueprint: b.clone(),
             initial_state: State {
                 step: 0,
                 inventory: Value::new([0, 0, 0, 0]),
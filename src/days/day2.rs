@@ -1,74 +1,422 @@
+use crate::utils::parse::{alpha, Input, PResult, Parser};
+use crate::utils::parse::{alt, lit, pair, parse_all, preceded, sep_by, terminated, uint};
+use itertools::Itertools;
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::fs::read_to_string;
 
-#[derive(Copy, Clone)]
-enum Rps {
-    Rock,
-    Paper,
-    Scissors,
+// The game is described by a config rather than match arms: the shapes and what each is worth,
+// which shapes beat which, what each outcome scores, what the letters in the guide stand for,
+// and for part two which outcome each letter asks for.
+const STANDARD: &str = "\
+shapes: Rock 1, Paper 2, Scissors 3
+beats: Paper Rock, Scissors Paper, Rock Scissors
+outcomes: lose 0, draw 3, win 6
+letters: A Rock, B Paper, C Scissors, X Rock, Y Paper, Z Scissors
+strategy: X lose, Y draw, Z win
+";
+
+// An index into the shapes of a Rules
+type Shape = usize;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Outcome {
+    Lose,
+    Draw,
+    Win,
 }
 
-fn parse_rps(c: char) -> Rps {
-    match c {
-        'A' | 'X' => Rps::Rock,
-        'B' | 'Y' => Rps::Paper,
-        'C' | 'Z' => Rps::Scissors,
-        _ => panic!(),
+impl Outcome {
+    fn name(self) -> &'static str {
+        ["lose", "draw", "win"][self as usize]
     }
 }
 
-fn score(opp: Rps, us: Rps) -> u32 {
-    match (opp, us) {
-        (Rps::Rock, Rps::Paper) => 6,
-        (Rps::Rock, Rps::Scissors) => 0,
-        (Rps::Paper, Rps::Rock) => 0,
-        (Rps::Paper, Rps::Scissors) => 6,
-        (Rps::Scissors, Rps::Rock) => 6,
-        (Rps::Scissors, Rps::Paper) => 0,
-        _ => 3,
+#[derive(Debug)]
+struct Rules {
+    names: Vec<String>,
+    values: Vec<u32>,
+    // beats[a][b] when a beats b
+    beats: Vec<Vec<bool>>,
+    outcome_scores: [u32; 3],
+    letters: HashMap<char, Shape>,
+    strategy: HashMap<char, Outcome>,
+}
+
+fn letter(input: &mut Input) -> PResult<char> {
+    match input.rest().chars().next() {
+        Some(c) if c.is_ascii_uppercase() => {
+            input.advance(1);
+            Ok(c)
+        }
+        _ => Err(input.error("a capital letter")),
     }
 }
 
-fn play(theirs: Rps, ours: Rps) -> u32 {
-    let round_score = score(theirs, ours);
-    let round_bonus = match ours {
-        Rps::Rock => 1,
-        Rps::Paper => 2,
-        Rps::Scissors => 3,
-    };
-    round_score + round_bonus
+// A line like "key: a, b, c"
+fn list<T>(key: &'static str, item: impl Parser<T>) -> impl Parser<Vec<T>> {
+    terminated(preceded(lit(key), sep_by(item, lit(", "))), lit("\n"))
 }
 
-fn move_for_strategy(opp: Rps, strategy: char) -> Rps {
-    match (opp, strategy) {
-        (Rps::Rock, 'X') => Rps::Scissors,
-        (Rps::Rock, 'Y') => Rps::Rock,
-        (Rps::Rock, 'Z') => Rps::Paper,
-        (Rps::Scissors, 'X') => Rps::Paper,
-        (Rps::Scissors, 'Y') => Rps::Scissors,
-        (Rps::Scissors, 'Z') => Rps::Rock,
-        (Rps::Paper, 'X') => Rps::Rock,
-        (Rps::Paper, 'Y') => Rps::Paper,
-        (Rps::Paper, 'Z') => Rps::Scissors,
-        _ => panic!(),
+// An item labelled with a letter, like "X lose"
+fn lettered<T>(item: impl Parser<T>) -> impl Parser<(char, T)> {
+    pair(terminated(letter, lit(" ")), item)
+}
+
+fn outcome(input: &mut Input) -> PResult<Outcome> {
+    alt(
+        lit("lose").map(|_| Outcome::Lose),
+        alt(
+            lit("draw").map(|_| Outcome::Draw),
+            lit("win").map(|_| Outcome::Win),
+        ),
+    )
+    .parse(input)
+}
+
+impl Rules {
+    fn parse(config: &str) -> PResult<Rules> {
+        let rules = |input: &mut Input| {
+            let value = pair(terminated(alpha(), lit(" ")), uint::<u32>());
+            let (names, values): (Vec<String>, Vec<u32>) =
+                list("shapes: ", value).parse(input)?.into_iter().unzip();
+
+            let shape = |input: &mut Input| {
+                let start = *input;
+                let name = alpha().parse(input)?;
+                names
+                    .iter()
+                    .position(|n| *n == name)
+                    .ok_or_else(|| start.error(format!("one of {}", names.join(", "))))
+            };
+
+            let mut beats = vec![vec![false; names.len()]; names.len()];
+            let start = *input;
+            let pairs = list("beats: ", pair(terminated(shape, lit(" ")), shape)).parse(input)?;
+            for (a, b) in pairs {
+                if a == b || beats[b][a] {
+                    return Err(start.error("shapes that don't beat each other both ways"));
+                }
+                beats[a][b] = true;
+            }
+
+            // Otherwise there'd be shapes you can't win or lose against
+            let n = names.len();
+            let fair = |s: Shape| (0..n).any(|o| beats[o][s]) && (0..n).any(|o| beats[s][o]);
+            if !(0..n).all(fair) {
+                return Err(start.error("shapes that each beat another and lose to another"));
+            }
+
+            let mut outcome_scores = [0; 3];
+            let scored = pair(terminated(outcome, lit(" ")), uint::<u32>());
+            for (o, score) in list("outcomes: ", scored).parse(input)? {
+                outcome_scores[o as usize] = score;
+            }
+
+            let letters = list("letters: ", lettered(shape)).parse(input)?;
+            let strategy = list("strategy: ", lettered(outcome)).parse(input)?;
+
+            Ok(Rules {
+                names,
+                values,
+                beats,
+                outcome_scores,
+                letters: letters.into_iter().collect(),
+                strategy: strategy.into_iter().collect(),
+            })
+        };
+
+        parse_all(rules, config)
+    }
+
+    fn parse_rps(&self, c: char) -> Shape {
+        self.letters[&c]
+    }
+
+    fn outcome(&self, theirs: Shape, ours: Shape) -> Outcome {
+        if self.beats[ours][theirs] {
+            Outcome::Win
+        } else if self.beats[theirs][ours] {
+            Outcome::Lose
+        } else {
+            Outcome::Draw
+        }
     }
+
+    fn play(&self, theirs: Shape, ours: Shape) -> u32 {
+        self.outcome_scores[self.outcome(theirs, ours) as usize] + self.values[ours]
+    }
+
+    fn move_for_strategy(&self, opp: Shape, strategy: char) -> Shape {
+        self.move_for_outcome(opp, self.strategy[&strategy])
+    }
+
+    // When more than one shape gets the outcome, the one that scores most. There's always at
+    // least one, as parse rejects rules with shapes nothing beats or that beat nothing.
+    fn move_for_outcome(&self, opp: Shape, wanted: Outcome) -> Shape {
+        (0..self.names.len())
+            .filter(|s| self.outcome(opp, *s) == wanted)
+            .max_by_key(|s| self.values[*s])
+            .unwrap()
+    }
+
+    // A shape that beats `opp`, preferring the one worth most
+    fn counter(&self, opp: Shape) -> Shape {
+        self.move_for_outcome(opp, Outcome::Win)
+    }
+}
+
+// What the second column of the guide might mean, as a letter for letter mapping
+#[derive(Clone, Debug, PartialEq)]
+enum Interpretation {
+    Shapes(Vec<(char, Shape)>),
+    Outcomes(Vec<(char, Outcome)>),
+}
+
+impl Interpretation {
+    // Written the way the config is, like "X Rock, Y Paper, Z Scissors"
+    fn describe(&self, rules: &Rules) -> String {
+        let meanings = match self {
+            Interpretation::Shapes(m) => m
+                .iter()
+                .map(|(l, s)| (*l, rules.names[*s].as_str()))
+                .collect(),
+            Interpretation::Outcomes(m) => {
+                m.iter().map(|(l, o)| (*l, o.name())).collect::<Vec<_>>()
+            }
+        };
+        meanings.iter().map(|(l, m)| format!("{l} {m}")).join(", ")
+    }
+}
+
+// A strategy guide boiled down to how often each pair of their shape and our letter turns up,
+// so scoring an interpretation doesn't depend on the length of the guide
+struct Guide {
+    counts: HashMap<(Shape, char), u32>,
+    letters: Vec<char>,
+    // Every round in order, for replaying the guide
+    moves: Vec<(Shape, char)>,
+}
+
+impl Guide {
+    fn read(rules: &Rules, input: &str) -> Guide {
+        let mut counts = HashMap::new();
+        let mut moves = vec![];
+
+        for l in input.lines().filter(|l| !l.is_empty()) {
+            let theirs = rules.parse_rps(l.chars().next().unwrap());
+            let ours = l.chars().nth(2).unwrap();
+            *counts.entry((theirs, ours)).or_insert(0) += 1;
+            moves.push((theirs, ours));
+        }
+
+        let letters = moves.iter().map(|m| m.1).unique().sorted().collect();
+        Guide {
+            counts,
+            letters,
+            moves,
+        }
+    }
+
+    fn move_for(rules: &Rules, interpretation: &Interpretation, theirs: Shape, c: char) -> Shape {
+        match interpretation {
+            Interpretation::Shapes(m) => m.iter().find(|(l, _)| *l == c).unwrap().1,
+            Interpretation::Outcomes(m) => {
+                let wanted = m.iter().find(|(l, _)| *l == c).unwrap().1;
+                rules.move_for_outcome(theirs, wanted)
+            }
+        }
+    }
+
+    fn score(&self, rules: &Rules, interpretation: &Interpretation) -> u32 {
+        self.counts
+            .iter()
+            .map(|(&(theirs, c), n)| {
+                let ours = Guide::move_for(rules, interpretation, theirs, c);
+                rules.play(theirs, ours) * n
+            })
+            .sum()
+    }
+
+    // Every way of giving each letter a different shape, or a different outcome
+    fn interpretations(&self, rules: &Rules) -> Vec<Interpretation> {
+        let k = self.letters.len();
+        let letters = || self.letters.iter().copied();
+
+        let shapes = (0..rules.names.len())
+            .permutations(k)
+            .map(|p| Interpretation::Shapes(letters().zip(p).collect()));
+        let outcomes = [Outcome::Lose, Outcome::Draw, Outcome::Win]
+            .into_iter()
+            .permutations(k)
+            .map(|p| Interpretation::Outcomes(letters().zip(p).collect()));
+
+        shapes.chain(outcomes).collect()
+    }
+
+    // The highest and lowest scoring interpretations, earliest first on ties. There are none
+    // when the guide uses more letters than there are shapes or outcomes to give them.
+    fn best_and_worst(
+        &self,
+        rules: &Rules,
+    ) -> Option<((u32, Interpretation), (u32, Interpretation))> {
+        let scored = self
+            .interpretations(rules)
+            .into_iter()
+            .map(|i| (self.score(rules, &i), i))
+            .collect::<Vec<_>>();
+
+        let best = scored.iter().rev().max_by_key(|s| s.0)?.clone();
+        let worst = scored.iter().min_by_key(|s| s.0)?.clone();
+        Some((best, worst))
+    }
+
+    // Replays our side of the guide over and over, against an opponent who ignores the guide
+    // and instead counters whatever we've played most often so far. Returns our score and
+    // theirs, each scored as if they were the one playing.
+    fn against_adaptive(
+        &self,
+        rules: &Rules,
+        interpretation: &Interpretation,
+        rounds: usize,
+    ) -> (u32, u32) {
+        let mut seen = vec![0; rules.names.len()];
+        let (mut ours_total, mut theirs_total) = (0, 0);
+
+        for (n, &(planned, c)) in self.moves.iter().cycle().take(rounds).enumerate() {
+            let theirs = if n == 0 {
+                planned
+            } else {
+                let favourite = (0..seen.len()).max_by_key(|s| (seen[*s], Reverse(*s)));
+                rules.counter(favourite.unwrap())
+            };
+
+            // Outcome letters are read against the opponent the guide expected
+            let ours = Guide::move_for(rules, interpretation, planned, c);
+            seen[ours] += 1;
+
+            ours_total += rules.play(theirs, ours);
+            theirs_total += rules.play(ours, theirs);
+        }
+
+        (ours_total, theirs_total)
+    }
+}
+
+// How the guide scores read in its best and worst ways, and how the best reading does over
+// `rounds` rounds, or one pass of the guide, against an opponent who counters our favourite
+pub fn strategies(rounds: Option<usize>) -> Result<String, String> {
+    let f = read_to_string("input/day2.txt").unwrap();
+    let rules = Rules::parse(STANDARD).unwrap();
+    let guide = Guide::read(&rules, &f);
+
+    let ((best, reading), (worst, worst_reading)) = guide
+        .best_and_worst(&rules)
+        .ok_or("the guide uses more letters than there are shapes or outcomes")?;
+
+    let rounds = rounds.unwrap_or(guide.moves.len());
+    let (ours, theirs) = guide.against_adaptive(&rules, &reading, rounds);
+
+    Ok(format!(
+        "best:  {} scores {best}\n\
+         worst: {} scores {worst}\n\
+         the best against an adaptive opponent, over {rounds} rounds: {ours} to {theirs}",
+        reading.describe(&rules),
+        worst_reading.describe(&rules),
+    ))
 }
 
 pub fn day_2() -> (String, String) {
     let f = read_to_string("input/day2.txt").unwrap();
+    let rules = Rules::parse(STANDARD).unwrap();
 
     let mut total_a = 0;
     let mut total_b = 0;
 
     for l in f.lines() {
         if !l.is_empty() {
-            let theirs = parse_rps(l.chars().next().unwrap());
-            let ours = parse_rps(l.chars().nth(2).unwrap());
-            let ours_2 = move_for_strategy(theirs, l.chars().nth(2).unwrap());
+            let theirs = rules.parse_rps(l.chars().next().unwrap());
+            let ours = rules.parse_rps(l.chars().nth(2).unwrap());
+            let ours_2 = rules.move_for_strategy(theirs, l.chars().nth(2).unwrap());
 
-            total_a += play(theirs, ours);
-            total_b += play(theirs, ours_2);
+            total_a += rules.play(theirs, ours);
+            total_b += rules.play(theirs, ours_2);
         }
     }
 
     (format!("{}", total_a), format!("{}", total_b))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LIZARD_SPOCK: &str = "\
+shapes: Rock 1, Paper 2, Scissors 3, Lizard 4, Spock 5
+beats: Rock Scissors, Rock Lizard, Paper Rock, Paper Spock, Scissors Paper, Scissors Lizard, \
+Lizard Paper, Lizard Spock, Spock Rock, Spock Scissors
+outcomes: lose 0, draw 3, win 6
+letters: A Rock, B Paper, C Scissors, D Lizard, E Spock, \
+V Rock, W Paper, X Scissors, Y Lizard, Z Spock
+strategy: X lose, Y draw, Z win
+";
+
+    #[test]
+    fn rule_sets() {
+        let rps = Rules::parse(STANDARD).unwrap();
+        assert_eq!(rps.play(rps.parse_rps('A'), rps.parse_rps('Y')), 8);
+        assert_eq!(rps.move_for_strategy(rps.parse_rps('C'), 'Z'), 0);
+
+        // Spock vaporises rock, and lizard is the best way to lose to scissors
+        let rpsls = Rules::parse(LIZARD_SPOCK).unwrap();
+        let (rock, scissors) = (rpsls.parse_rps('A'), rpsls.parse_rps('C'));
+        assert_eq!(rpsls.play(rock, rpsls.parse_rps('Z')), 11);
+        assert_eq!(
+            rpsls.names[rpsls.move_for_strategy(scissors, 'X')],
+            "Lizard"
+        );
+        assert_eq!(rpsls.names[rpsls.move_for_strategy(rock, 'Z')], "Spock");
+
+        let bad = STANDARD.replace("Rock Scissors", "Rock Stone");
+        let err = Rules::parse(&bad).unwrap_err();
+        assert_eq!(err.location, Some((2, 41)));
+        assert_eq!(err.expected.to_string(), "one of Rock, Paper, Scissors");
+
+        // Nothing beats rock, so there'd be no way to win against it
+        let unfair = STANDARD.replace("Paper Rock", "Rock Paper");
+        let err = Rules::parse(&unfair).unwrap_err();
+        assert_eq!(err.location, Some((2, 1)));
+        assert_eq!(
+            err.expected.to_string(),
+            "shapes that each beat another and lose to another"
+        );
+    }
+
+    #[test]
+    fn strategy_guide() {
+        let rps = Rules::parse(STANDARD).unwrap();
+        let guide = Guide::read(&rps, "A Y\nB X\nC Z\n");
+        let as_shapes = Interpretation::Shapes(vec![('X', 0), ('Y', 1), ('Z', 2)]);
+        assert_eq!(guide.score(&rps, &as_shapes), 15);
+        assert_eq!(guide.interpretations(&rps).len(), 12);
+
+        // Winning every round beats any mix of outcomes, and losing every round is worst
+        let (best, worst) = guide.best_and_worst(&rps).unwrap();
+        assert_eq!(best.0, 24);
+        assert_eq!(worst.0, 6);
+        assert_eq!(
+            worst.1,
+            Interpretation::Shapes(vec![('X', 0), ('Y', 2), ('Z', 1)])
+        );
+
+        // Always playing rock gets countered from the second round on
+        let rock = Interpretation::Shapes(vec![('X', 0), ('Y', 0), ('Z', 0)]);
+        assert_eq!(guide.against_adaptive(&rps, &rock, 10), (4 + 9, 4 + 9 * 8));
+        assert_eq!(rock.describe(&rps), "X Rock, Y Rock, Z Rock");
+
+        // Four letters can't each have a different outcome, or one of three shapes
+        let guide = Guide::read(&rps, "A W\nB X\nC Y\nA Z\n");
+        assert!(guide.interpretations(&rps).is_empty());
+        assert_eq!(guide.best_and_worst(&rps), None);
+    }
+}
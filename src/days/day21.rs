@@ -1,18 +1,19 @@
+use crate::utils::num::{Int, Integer, NResult};
 use std::collections::HashMap;
 use std::fs::read_to_string;
 use std::ops::Deref;
 
 // In order to solve Part 2, values can be known, unknown (the input const), or partial evaluations
 #[derive(Debug, Clone)]
-enum Value {
-    Known(isize),
+enum Value<N> {
+    Known(N),
     Unknown,
-    Partial(Op, Box<Value>, Box<Value>),
+    Partial(Op<N>, Box<Value<N>>, Box<Value<N>>),
 }
 
 #[derive(Debug, Clone)]
-enum Op {
-    KnownConst(isize),
+enum Op<N> {
+    KnownConst(N),
     UnknownConst,
     Add(String, String),
     Subtract(String, String),
@@ -21,69 +22,70 @@ enum Op {
     Equal(String, String),
 }
 
-impl Op {
-    fn parse(s: &str) -> (String, Op) {
-        let (name, rest) = s.split_at(4);
-        let (_, op) = rest.split_at(2);
-
-        let op = if op.len() <= 4 {
-            Op::KnownConst(str::parse::<isize>(op).unwrap())
-        } else {
-            let a = op[0..4].to_string();
-            let b = op[7..11].to_string();
-            let o = op.chars().nth(5).unwrap();
-
-            if o == '+' {
-                Op::Add(a, b)
-            } else if o == '-' {
-                Op::Subtract(a, b)
-            } else if o == '*' {
-                Op::Multiply(a, b)
-            } else {
-                Op::Divide(a, b)
+impl<N: Integer> Op<N> {
+    // Either a number, or two names and an operator between them. Numbers can be any length,
+    // so they're told apart by there being no operator, not by how long the line is.
+    fn parse(s: &str) -> (String, Op<N>) {
+        let (name, rest) = s.split_once(": ").unwrap();
+
+        let op = match rest.split(' ').collect::<Vec<&str>>()[..] {
+            [a, o, b] => {
+                let (a, b) = (a.to_string(), b.to_string());
+                match o {
+                    "+" => Op::Add(a, b),
+                    "-" => Op::Subtract(a, b),
+                    "*" => Op::Multiply(a, b),
+                    "/" => Op::Divide(a, b),
+                    _ => panic!("unknown operator {o:?} in {s:?}"),
+                }
             }
+            [n] => Op::KnownConst(n.parse::<N>().ok().unwrap()),
+            _ => panic!("can't read {s:?}"),
         };
 
         (name.to_string(), op)
     }
 }
 
-struct Machine {
-    ops: HashMap<String, Op>,
+struct Machine<N> {
+    ops: HashMap<String, Op<N>>,
 }
 
-impl Machine {
-    fn execute_op(&self, scratch: &mut HashMap<String, Value>, name: &str) -> Value {
+impl<N: Integer> Machine<N> {
+    fn execute_op(&self, scratch: &mut HashMap<String, Value<N>>, name: &str) -> NResult<Value<N>> {
         if let Some(v) = scratch.get(name) {
-            return v.clone();
+            return Ok(v.clone());
         }
 
         let o = self.ops.get(name).unwrap();
 
-        let mut value_op = |a, b, f: fn(isize, isize) -> isize| {
-            let va = self.execute_op(scratch, a);
-            let vb = self.execute_op(scratch, b);
+        let mut value_op = |a, b, f: fn(&N, &N) -> NResult<N>| {
+            let va = self.execute_op(scratch, a)?;
+            let vb = self.execute_op(scratch, b)?;
 
-            match (&va, &vb) {
-                (Value::Known(ia), Value::Known(ib)) => Value::Known(f(*ia, *ib)),
+            Ok(match (&va, &vb) {
+                (Value::Known(ia), Value::Known(ib)) => {
+                    let v = f(ia, ib).map_err(|e| e.context(format!("evaluating {name}")))?;
+                    Value::Known(v)
+                }
                 _ => Value::Partial(o.clone(), Box::new(va.clone()), Box::new(vb.clone())),
-            }
+            })
         };
 
         let res = match o {
-            Op::KnownConst(v) => Value::Known(*v),
+            Op::KnownConst(v) => Value::Known(v.clone()),
             Op::UnknownConst => Value::Unknown,
-            Op::Add(a, b) => value_op(a, b, |a, b| a + b),
-            Op::Subtract(a, b) => value_op(a, b, |a, b| a - b),
-            Op::Multiply(a, b) => value_op(a, b, |a, b| a * b),
-            Op::Divide(a, b) => value_op(a, b, |a, b| a / b),
-            Op::Equal(a, b) => value_op(a, b, |a, b| if a == b { 1 } else { 0 }),
+            Op::Add(a, b) => value_op(a, b, N::add)?,
+            Op::Subtract(a, b) => value_op(a, b, N::sub)?,
+            Op::Multiply(a, b) => value_op(a, b, N::mul)?,
+            Op::Divide(a, b) => value_op(a, b, N::div)?,
+            Op::Equal(a, b) => value_op(a, b, |a, b| Ok(N::from_i64((a == b) as i64)))?,
         };
 
         scratch.insert(name.to_string(), res.clone());
-        res
+        Ok(res)
     }
-    fn run(&self) -> Value {
+    fn run(&self) -> NResult<Value<N>> {
         let mut scratch = HashMap::new();
         self.execute_op(&mut scratch, "root")
     }
@@ -93,46 +95,49 @@ impl Machine {
 // (a + 5) = 6
 // -> a = 1
 // Only what is required to complete the puzzle is implemented because the code is already pretty ugly.
-fn simplify_equality(unknown: Value, known: Value) -> (Value, Value) {
-    match (unknown, known) {
+fn simplify_equality<N: Integer>(
+    unknown: Value<N>,
+    known: Value<N>,
+) -> NResult<(Value<N>, Value<N>)> {
+    Ok(match (unknown, known) {
         (Value::Partial(Op::Divide(_, _), a, b), Value::Known(i)) => match (a.deref(), b.deref()) {
-            (a, Value::Known(b)) => (a.clone(), Value::Known(i * b)),
+            (a, Value::Known(b)) => (a.clone(), Value::Known(i.mul(b)?)),
             _ => panic!(),
         },
         (Value::Partial(Op::Add(_, _), a, b), Value::Known(i)) => match (a.deref(), b.deref()) {
-            (a, Value::Known(b)) => (a.clone(), Value::Known(i - b)),
-            (Value::Known(b), a) => (a.clone(), Value::Known(i - b)),
+            (a, Value::Known(b)) => (a.clone(), Value::Known(i.sub(b)?)),
+            (Value::Known(b), a) => (a.clone(), Value::Known(i.sub(b)?)),
             _ => panic!(),
         },
         (Value::Partial(Op::Multiply(_, _), a, b), Value::Known(i)) => {
             match (a.deref(), b.deref()) {
-                (a, Value::Known(b)) => (a.clone(), Value::Known(i / b)),
-                (Value::Known(b), a) => (a.clone(), Value::Known(i / b)),
+                (a, Value::Known(b)) => (a.clone(), Value::Known(i.div(b)?)),
+                (Value::Known(b), a) => (a.clone(), Value::Known(i.div(b)?)),
                 _ => panic!(),
             }
         }
         (Value::Partial(Op::Subtract(_, _), a, b), Value::Known(i)) => {
             match (a.deref(), b.deref()) {
-                (a, Value::Known(b)) => (a.clone(), Value::Known(i + b)),
-                (Value::Known(a), b) => (b.clone(), Value::Known(a - i)),
+                (a, Value::Known(b)) => (a.clone(), Value::Known(i.add(b)?)),
+                (Value::Known(a), b) => (b.clone(), Value::Known(a.sub(&i)?)),
                 _ => panic!(),
             }
         }
         _ => panic!(),
-    }
+    })
 }
 
-fn simplify_equality_loop(unknown: Value, known: Value) -> isize {
+fn simplify_equality_loop<N: Integer>(unknown: Value<N>, known: Value<N>) -> NResult<N> {
     let mut vals = (unknown, known);
     loop {
-        vals = simplify_equality(vals.0, vals.1);
-        if let Value::Known(i) = vals.1 {
-            return i;
+        vals = simplify_equality(vals.0, vals.1).map_err(|e| e.context("solving for humn"))?;
+        if let (Value::Unknown, Value::Known(i)) = vals {
+            return Ok(i);
         }
     }
 }
 
-fn solve_equality(value: Value) -> isize {
+fn solve_equality<N: Integer>(value: Value<N>) -> NResult<N> {
     if let Value::Partial(_, a, b) = value {
         if matches!(*a.deref(), Value::Known(_)) {
             simplify_equality_loop(b.deref().clone(), a.deref().clone())
@@ -144,20 +149,18 @@ fn solve_equality(value: Value) -> isize {
     }
 }
 
-pub fn day_21() -> (String, String) {
-    let f = read_to_string("input/day21.txt").unwrap();
-
+fn solve<N: Integer>(input: &str) -> NResult<(N, N)> {
     let mut m = Machine {
         ops: HashMap::new(),
     };
 
-    for l in f.lines() {
+    for l in input.lines() {
         let (name, op) = Op::parse(l);
         m.ops.insert(name, op);
     }
 
     // Part A
-    let res = m.run();
+    let res = m.run()?;
     let part_a = match res {
         Value::Known(i) => i,
         _ => panic!(),
@@ -171,9 +174,62 @@ pub fn day_21() -> (String, String) {
     m.ops.insert("humn".to_string(), Op::UnknownConst);
 
     // Get partial result out of machine
-    let res = m.run();
+    let res = m.run()?;
     // then solve for the generated equation
-    let eq = solve_equality(res);
+    let eq = solve_equality(res)?;
+
+    Ok((part_a, eq))
+}
+
+pub fn day_21() -> (String, String) {
+    let f = read_to_string("input/day21.txt").unwrap();
+
+    let (part_a, eq) = solve::<Int>(&f).unwrap_or_else(|e| panic!("day 21: {e}"));
 
     (format!("{}", part_a), format!("{}", eq))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32
+";
+
+    #[test]
+    fn overflow() {
+        assert_eq!(solve::<i64>(EXAMPLE), Ok((152, 301)));
+
+        // A constant as long as an operation, and a sum that doesn't fit
+        let big = "root: aaaa + bbbb\naaaa: 9223372036854775807\nbbbb: 1\nhumn: 0\n";
+        let err = solve::<i64>(big).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "9223372036854775807 + 1 overflowed, while evaluating root"
+        );
+
+        // Part one fits, but undoing the subtraction to find humn doesn't
+        let big =
+            "root: aaaa + bbbb\naaaa: humn - cccc\ncccc: 9223372036854775807\nbbbb: 1\nhumn: 0\n";
+        let err = solve::<i64>(big).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1 + 9223372036854775807 overflowed, while solving for humn"
+        );
+    }
+}
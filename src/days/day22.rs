@@ -1,22 +1,14 @@
-use regex::Regex;
+use crate::utils::geom::{Dir4, Point2};
+use crate::utils::grid::Grid;
+use crate::utils::parse::{
+    alt, lines, lit, many, pair, parse_all, rest_of_line, terminated, uint, Parser,
+};
 use std::fs::read_to_string;
 
 #[derive(Copy, Clone)]
 struct State {
-    x: isize,
-    y: isize,
-    rotation: isize,
-}
-
-impl State {
-    fn rotate(&mut self, dir: isize) {
-        self.rotation += dir;
-        if self.rotation < 0 {
-            self.rotation = 3;
-        } else if self.rotation > 3 {
-            self.rotation = 0;
-        }
-    }
+    pos: Point2,
+    facing: Dir4,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -35,53 +27,47 @@ enum Instruction {
 
 struct Board {
     cube: bool,
-    lines: Vec<Vec<Piece>>,
+    pieces: Grid<Piece>,
 }
 
 impl Board {
     fn next_piece_cube(&self, state: State) -> State {
         // Not particularly happy with this code - there must be a way to generalize
         // But it's pretty fast, so...
-        let (dx, dy) = [(1, 0), (0, 1), (-1, 0), (0, -1)][state.rotation as usize];
-
         let mut next_state = State {
-            x: state.x + dx,
-            y: state.y + dy,
-            rotation: state.rotation,
+            pos: state.pos + state.facing,
+            facing: state.facing,
         };
 
-        let sq = |x, y| ((x + 50) / 50, (y + 50) / 50);
+        let sq = |p: Point2| ((p.x + 50) / 50, (p.y + 50) / 50);
 
-        let curs = sq(state.x, state.y);
-        let news = sq(next_state.x, next_state.y);
+        let curs = sq(state.pos);
+        let news = sq(next_state.pos);
 
+        // Each of these enters face b from one of its edges, i along that edge
         let to_left = |i, b: (isize, isize)| State {
-            x: (b.0 - 1) * 50,
-            y: (b.1 - 1) * 50 + i,
-            rotation: 0,
+            pos: Point2::new((b.0 - 1) * 50, (b.1 - 1) * 50 + i),
+            facing: Dir4::East,
         };
 
         let to_right = |i, b: (isize, isize)| State {
-            x: (b.0 - 1) * 50 + 49,
-            y: (b.1 - 1) * 50 + i,
-            rotation: 2,
+            pos: Point2::new((b.0 - 1) * 50 + 49, (b.1 - 1) * 50 + i),
+            facing: Dir4::West,
         };
 
         let to_up = |i, b: (isize, isize)| State {
-            x: (b.0 - 1) * 50 + i,
-            y: (b.1 - 1) * 50,
-            rotation: 1,
+            pos: Point2::new((b.0 - 1) * 50 + i, (b.1 - 1) * 50),
+            facing: Dir4::South,
         };
 
         let to_down = |i, b: (isize, isize)| State {
-            x: (b.0 - 1) * 50 + i,
-            y: (b.1 - 1) * 50 + 49,
-            rotation: 3,
+            pos: Point2::new((b.0 - 1) * 50 + i, (b.1 - 1) * 50 + 49),
+            facing: Dir4::North,
         };
 
         if curs != news {
-            let x = state.x % 50;
-            let y = state.y % 50;
+            let x = state.pos.x % 50;
+            let y = state.pos.y % 50;
             let y_flip = 49 - y;
 
             next_state = match (curs, news) {
@@ -127,31 +113,17 @@ impl Board {
     }
 
     fn next_piece(&self, state: State) -> State {
-        let (mut px, mut py) = (state.x, state.y);
-
-        let (dx, dy) = [(1, 0), (0, 1), (-1, 0), (0, -1)][state.rotation as usize];
+        let mut p = state.pos;
 
         loop {
-            px += dx;
-            py += dy;
-
-            if px < 0 {
-                px = (self.lines[0].len() - 1) as isize;
-            } else if px >= self.lines[0].len() as isize {
-                px = 0;
-            }
-
-            if py < 0 {
-                py = (self.lines.len() - 1) as isize;
-            } else if py >= self.lines.len() as isize {
-                py = 0;
-            }
+            let next = p + state.facing;
+            let (wx, wy) = self.pieces.wrap(next.x, next.y);
+            p = Point2::new(wx as isize, wy as isize);
 
-            if self.lines[py as usize][px as usize] != Piece::OffMap {
+            if self.pieces[(wx, wy)] != Piece::OffMap {
                 return State {
-                    x: px,
-                    y: py,
-                    rotation: state.rotation,
+                    pos: p,
+                    facing: state.facing,
                 };
             }
         }
@@ -165,7 +137,7 @@ impl Board {
                 self.next_piece(state)
             };
 
-            if self.lines[next.y as usize][next.x as usize] == Piece::Blocked {
+            if self.pieces[(next.pos.x as usize, next.pos.y as usize)] == Piece::Blocked {
                 return state;
             } else {
                 state = next;
@@ -176,29 +148,17 @@ impl Board {
     }
 
     fn read(lines: &[&str]) -> Board {
-        let len = lines.iter().map(|v| v.len()).max().unwrap();
-        let mut board = Board {
-            lines: vec![],
+        let pieces = Grid::from_lines(lines.iter().copied(), |c| match c {
+            ' ' => Piece::OffMap,
+            '.' => Piece::Open,
+            '#' => Piece::Blocked,
+            _ => panic!(),
+        });
+
+        Board {
+            pieces,
             cube: false,
-        };
-
-        for l in lines {
-            let chars = l.chars().collect::<Vec<char>>();
-            let mut board_line = vec![];
-
-            for n in 0..len {
-                board_line.push(match *chars.get(n).unwrap_or(&' ') {
-                    ' ' => Piece::OffMap,
-                    '.' => Piece::Open,
-                    '#' => Piece::Blocked,
-                    _ => panic!(),
-                })
-            }
-
-            board.lines.push(board_line);
         }
-
-        board
     }
 }
 
@@ -214,20 +174,18 @@ impl Game {
             board,
             instructions,
             state: State {
-                x: 0,
-                y: 0,
-                rotation: 0,
+                pos: Point2::ORIGIN,
+                facing: Dir4::East,
             },
         }
     }
 
     fn reset(&mut self, cube: bool) {
         self.board.cube = cube;
-        for (x, piece) in self.board.lines[0].iter().enumerate() {
+        for (x, piece) in self.board.pieces.row(0).iter().enumerate() {
             if matches!(piece, Piece::Open) {
-                self.state.x = x as isize;
-                self.state.y = 0;
-                self.state.rotation = 0;
+                self.state.pos = Point2::new(x as isize, 0);
+                self.state.facing = Dir4::East;
                 return;
             }
         }
@@ -238,10 +196,10 @@ impl Game {
         for i in &self.instructions {
             match i {
                 Instruction::Left => {
-                    self.state.rotate(-1);
+                    self.state.facing = self.state.facing.rotate_left();
                 }
                 Instruction::Right => {
-                    self.state.rotate(1);
+                    self.state.facing = self.state.facing.rotate_right();
                 }
                 Instruction::Move(length) => {
                     self.state = self.board.move_piece(self.state, *length);
@@ -249,30 +207,29 @@ impl Game {
             }
         }
 
-        (self.state.y + 1) * 1000 + (self.state.x + 1) * 4 + self.state.rotation
+        let State { pos, facing } = self.state;
+        (pos.y + 1) * 1000 + (pos.x + 1) * 4 + facing.index() as isize
     }
 }
 
 pub fn day_22() -> (String, String) {
     let f = read_to_string("input/day22.txt").unwrap();
 
-    let lines = f.lines().collect::<Vec<&str>>();
-
-    let board = Board::read(&lines[0..lines.len() - 2]);
-    let path = lines[lines.len() - 1];
-
-    let re = Regex::new(r"(\d+|R|L)").unwrap();
-    let mut instructions = vec![];
-
-    for c in re.captures_iter(path) {
-        instructions.push(if &c[0] == "L" {
-            Instruction::Left
-        } else if &c[0] == "R" {
-            Instruction::Right
-        } else {
-            Instruction::Move(str::parse::<usize>(&c[0]).unwrap())
-        })
-    }
+    let turn = alt(
+        lit("L").map(|_| Instruction::Left),
+        lit("R").map(|_| Instruction::Right),
+    );
+    let instruction = alt(turn, uint().map(Instruction::Move));
+    let (map, instructions) = parse_all(
+        pair(
+            terminated(lines(rest_of_line()), lit("\n\n")),
+            many(instruction),
+        ),
+        &f,
+    )
+    .unwrap();
+
+    let board = Board::read(&map.iter().map(|l| l.as_str()).collect::<Vec<&str>>());
 
     let mut g = Game::new(board, instructions);
 
@@ -1,3 +1,6 @@
+use crate::utils::bit_grid::{BitGrid, BitView};
+use crate::utils::grid::Grid;
+use crate::utils::viz::{Frame, Visualize};
 use std::fs::read_to_string;
 
 // With the exception of using an intermediate mask that represents surrounding dwarves,
@@ -7,6 +10,24 @@ use std::fs::read_to_string;
 // I'm a little sad this didn't go faster - 1000 rounds takes 42 milliseconds on a 3900X.
 // To speed it up, it either needs lower level SIMD magic, or it needs something like HashLife.
 
+// BitBoard below is the SIMD magic, or at least the poor man's version of it. Elves are a
+// BitGrid, and each rule is applied to every elf at once with whole row shifts. It runs in
+// about a third of the time of the sparse version, which is kept around to check it against.
+
+trait Simulation {
+    fn step(&mut self);
+    fn score(&self) -> usize;
+    fn round(&self) -> usize;
+    fn finished(&self) -> bool;
+}
+
+#[derive(Copy, Clone)]
+enum Engine {
+    #[allow(unused)]
+    Sparse,
+    Bits,
+}
+
 struct Dwarf {
     dest: Option<(usize, usize)>,
     pos: (usize, usize),
@@ -22,16 +43,50 @@ enum Square {
 }
 
 struct Board {
-    width: usize,
-    height: usize,
     round: usize,
     finished: bool,
     moved: usize,
-    squares: Vec<Square>,
+    squares: Grid<Square>,
     dwarves: Vec<Dwarf>,
 }
 
+const PADDING: usize = 60;
+
 impl Board {
+    fn read(input: &str) -> Board {
+        let lines = input.lines().collect::<Vec<&str>>();
+        let mut dwarves = vec![];
+        let mut squares = Grid::new(
+            PADDING * 2 + lines.first().map_or(0, |l| l.len()),
+            PADDING * 2 + lines.len(),
+            Square::Empty,
+        );
+
+        for (y, l) in lines.iter().enumerate() {
+            for (x, c) in l.chars().enumerate() {
+                if c == '#' {
+                    let pos = (PADDING + x, PADDING + y);
+                    squares[pos] = Square::Dwarf(dwarves.len() as u16);
+                    dwarves.push(Dwarf {
+                        dest: None,
+                        awake: true,
+                        pos,
+                    });
+                }
+            }
+        }
+
+        Board {
+            round: 0,
+            moved: 0,
+            finished: false,
+            squares,
+            dwarves,
+        }
+    }
+}
+
+impl Simulation for Board {
     fn step(&mut self) {
         const CHECKS: [((isize, isize), u8); 4] = [
             ((0, -1), 0b111),
@@ -53,35 +108,35 @@ impl Board {
             // to do next. Manually inlining yields the desired speedup, but I'm curious what
             // matches!() is going to compile down to. Not breaking out Godbolt for this...
 
-            if matches!(self.squares[(y - 1) * self.width + x - 1], Square::Dwarf(_)) {
+            if matches!(self.squares[(x - 1, y - 1)], Square::Dwarf(_)) {
                 dwarf_map |= 1;
             }
 
-            if matches!(self.squares[(y - 1) * self.width + x], Square::Dwarf(_)) {
+            if matches!(self.squares[(x, y - 1)], Square::Dwarf(_)) {
                 dwarf_map |= 1 << 1;
             }
 
-            if matches!(self.squares[(y - 1) * self.width + x + 1], Square::Dwarf(_)) {
+            if matches!(self.squares[(x + 1, y - 1)], Square::Dwarf(_)) {
                 dwarf_map |= 1 << 2;
             }
 
-            if matches!(self.squares[(y) * self.width + x - 1], Square::Dwarf(_)) {
+            if matches!(self.squares[(x - 1, y)], Square::Dwarf(_)) {
                 dwarf_map |= 1 << 3;
             }
 
-            if matches!(self.squares[(y) * self.width + x + 1], Square::Dwarf(_)) {
+            if matches!(self.squares[(x + 1, y)], Square::Dwarf(_)) {
                 dwarf_map |= 1 << 4;
             }
 
-            if matches!(self.squares[(y + 1) * self.width + x - 1], Square::Dwarf(_)) {
+            if matches!(self.squares[(x - 1, y + 1)], Square::Dwarf(_)) {
                 dwarf_map |= 1 << 5;
             }
 
-            if matches!(self.squares[(y + 1) * self.width + x], Square::Dwarf(_)) {
+            if matches!(self.squares[(x, y + 1)], Square::Dwarf(_)) {
                 dwarf_map |= 1 << 6;
             }
 
-            if matches!(self.squares[(y + 1) * self.width + x + 1], Square::Dwarf(_)) {
+            if matches!(self.squares[(x + 1, y + 1)], Square::Dwarf(_)) {
                 dwarf_map |= 1 << 7;
             }
 
@@ -96,12 +151,12 @@ impl Board {
                 if dwarf_map & mask == 0 {
                     let tx = (x as isize + dir.0) as usize;
                     let ty = (y as isize + dir.1) as usize;
-                    let to = self.squares[ty * self.width + tx];
+                    let to = self.squares[(tx, ty)];
 
                     if to == Square::Marked {
-                        self.squares[ty * self.width + tx] = Square::OverMarked;
+                        self.squares[(tx, ty)] = Square::OverMarked;
                     } else if to == Square::Empty {
-                        self.squares[ty * self.width + tx] = Square::Marked;
+                        self.squares[(tx, ty)] = Square::Marked;
                     }
 
                     self.dwarves[d].dest = Some((tx, ty));
@@ -117,8 +172,8 @@ impl Board {
             if let Some((dx, dy)) = self.dwarves[d].dest {
                 let (px, py) = self.dwarves[d].pos;
 
-                let dest_square = dy * self.width + dx;
-                let current_square = py * self.width + px;
+                let dest_square = (dx, dy);
+                let current_square = (px, py);
 
                 if self.squares[dest_square] == Square::OverMarked {
                     self.squares[dest_square] = Square::Empty;
@@ -130,11 +185,9 @@ impl Board {
 
                     for cx in -1..=1 {
                         for cy in -1..=1 {
-                            if let Square::Dwarf(od) = self.squares[((dy as isize + cy) as usize)
-                                * self.width
-                                + (dx as isize + cx) as usize]
-                            {
-                                self.dwarves[od as usize].awake = true;
+                            let (nx, ny) = (dx as isize + cx, dy as isize + cy);
+                            if let Some(Square::Dwarf(od)) = self.squares.get(nx, ny) {
+                                self.dwarves[*od as usize].awake = true;
                             }
                         }
                     }
@@ -150,18 +203,15 @@ impl Board {
     }
 
     fn score(&self) -> usize {
-        let mut low = (self.width - 1, self.height - 1);
+        let mut low = (self.squares.width() - 1, self.squares.height() - 1);
         let mut high = (0, 0);
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let sq = self.squares[y * self.width + x];
-                if let Square::Dwarf(_) = sq {
-                    low.0 = low.0.min(x);
-                    low.1 = low.1.min(y);
-                    high.0 = high.0.max(x);
-                    high.1 = high.1.max(y);
-                }
+        for ((x, y), sq) in self.squares.iter() {
+            if let Square::Dwarf(_) = sq {
+                low.0 = low.0.min(x);
+                low.1 = low.1.min(y);
+                high.0 = high.0.max(x);
+                high.1 = high.1.max(y);
             }
         }
 
@@ -169,70 +219,238 @@ impl Board {
 
         for y in low.1..=high.1 {
             for x in low.0..=high.0 {
-                let sq = self.squares[y * self.width + x];
-                if let Square::Empty = sq {
+                if let Square::Empty = self.squares[(x, y)] {
                     score += 1;
                 }
             }
         }
         score
     }
+
+    fn round(&self) -> usize {
+        self.round
+    }
+
+    fn finished(&self) -> bool {
+        self.finished
+    }
 }
 
-const PADDING: usize = 60;
+// How much room to add around the field when an elf reaches its edge
+const GROWTH: usize = 16;
 
-pub fn day_23() -> (String, String) {
-    let f = read_to_string("input/day23.txt").unwrap();
+// N S W E, the order the elves consider moving in on the first round
+const DIRECTIONS: [(isize, isize); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+struct BitBoard {
+    elves: BitGrid,
+    round: usize,
+    finished: bool,
+}
+
+impl BitBoard {
+    fn read(input: &str) -> BitBoard {
+        let lines = input.lines().collect::<Vec<&str>>();
+        let width = lines.first().map_or(0, |l| l.len());
+        let mut elves = BitGrid::new(width, lines.len());
+
+        for (y, l) in lines.iter().enumerate() {
+            for (x, c) in l.chars().enumerate() {
+                elves.set(x, y, c == '#');
+            }
+        }
 
-    let mut in_squares = vec![];
-    let mut dwarves = vec![];
-    let width = PADDING * 2 + 74;
-    let height = PADDING * 2 + 74;
-
-    for (y, l) in f.lines().enumerate() {
-        in_squares.append(&mut vec![Square::Empty; PADDING]);
-        for (x, c) in l.chars().enumerate() {
-            in_squares.push(if c == '.' {
-                Square::Empty
-            } else {
-                let num = dwarves.len() as u16;
-                dwarves.push(Dwarf {
-                    dest: None,
-                    awake: true,
-                    pos: (PADDING + x, PADDING + y),
-                });
-                Square::Dwarf(num)
-            });
-        }
-        in_squares.append(&mut vec![Square::Empty; PADDING]);
+        BitBoard {
+            elves,
+            round: 0,
+            finished: false,
+        }
     }
+}
 
-    let mut squares = vec![Square::Empty; width * PADDING];
-    squares.append(&mut in_squares);
-    squares.append(&mut vec![Square::Empty; width * PADDING]);
-
-    let mut b = Board {
-        width,
-        height,
-        round: 0,
-        moved: 0,
-        finished: false,
-        squares,
-        dwarves,
-    };
+impl Simulation for BitBoard {
+    fn step(&mut self) {
+        // Keep an empty ring around the elves, so nobody tries to move off the grid. With no
+        // elves at all there's nobody to move.
+        let Some((x0, y0, x1, y1)) = self.elves.bounding_box() else {
+            self.finished = true;
+            self.round += 1;
+            return;
+        };
+        if x0 == 0 || y0 == 0 || x1 + 1 == self.elves.width() || y1 + 1 == self.elves.height() {
+            self.elves = self.elves.pad(GROWTH, GROWTH, GROWTH, GROWTH);
+        }
+
+        let elves = &self.elves;
+
+        // Runs of three cells centred on each elf, horizontally and vertically. Shifting a
+        // horizontal run down one row gives the three cells north of each square, and so on.
+        let mut across = elves.clone();
+        across.apply(&elves.shifted(-1, 0), |a, b| a | b);
+        across.apply(&elves.shifted(1, 0), |a, b| a | b);
+        let mut down = elves.clone();
+        down.apply(&elves.shifted(0, -1), |a, b| a | b);
+        down.apply(&elves.shifted(0, 1), |a, b| a | b);
+
+        let blocked = [
+            across.shifted(0, 1),
+            across.shifted(0, -1),
+            down.shifted(1, 0),
+            down.shifted(-1, 0),
+        ];
+
+        // Elves with nobody around them stay put
+        let mut undecided = blocked[0].clone();
+        for b in &blocked[1..] {
+            undecided.apply(b, |a, b| a | b);
+        }
+        undecided.apply(elves, |a, b| a & b);
+
+        // Each elf proposes the first unblocked direction, starting from this round's
+        let mut targets = vec![BitGrid::new(0, 0); 4];
+        for n in 0..4 {
+            let d = (n + self.round) % 4;
+            let mut proposed = undecided.clone();
+            proposed.apply(&blocked[d], |a, b| a & !b);
+            undecided.apply(&proposed, |a, b| a & !b);
+            targets[d] = proposed.shifted(DIRECTIONS[d].0, DIRECTIONS[d].1);
+        }
 
-    for _ in 0..10 {
+        // Two elves can only propose the same square from opposite sides, so collisions are
+        // found by overlapping the north movers with the south, and the west with the east
+        let mut arrived = BitGrid::new(elves.width(), elves.height());
+        let mut departed = BitGrid::new(elves.width(), elves.height());
+
+        for d in 0..4 {
+            let mut landed = targets[d].clone();
+            landed.apply(&targets[d ^ 1], |a, b| a & !b);
+
+            let (dx, dy) = DIRECTIONS[d];
+            departed.apply(&landed.shifted(-dx, -dy), |a, b| a | b);
+            arrived.apply(&landed, |a, b| a | b);
+        }
+
+        self.elves.apply(&departed, |a, b| a & !b);
+        self.elves.apply(&arrived, |a, b| a | b);
+
+        self.finished = arrived.count() == 0;
+        self.round += 1;
+    }
+
+    // No elves cover no ground, so there's none empty either
+    fn score(&self) -> usize {
+        match self.elves.bounding_box() {
+            Some((x0, y0, x1, y1)) => (x1 - x0 + 1) * (y1 - y0 + 1) - self.elves.count(),
+            None => 0,
+        }
+    }
+
+    fn round(&self) -> usize {
+        self.round
+    }
+
+    fn finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl Visualize for BitBoard {
+    fn frame(&self) -> Frame {
+        self.elves.frame()
+    }
+}
+
+// One frame per round, until the elves stop moving. The grid grows as they spread out.
+pub fn visualize(frames: &mut dyn FnMut(&dyn Visualize)) {
+    let f = read_to_string("input/day23.txt").unwrap();
+    let mut b = BitBoard::read(&f);
+
+    frames(&b);
+    while !b.finished() {
         b.step();
+        frames(&b);
     }
+}
 
-    let part_a = b.score();
+// Elves that keep spreading out are given up on after this many rounds
+const MAX_ROUNDS: usize = 1010;
+
+fn simulate(input: &str, engine: Engine, limit: usize) -> Result<(usize, usize), String> {
+    let mut b: Box<dyn Simulation> = match engine {
+        Engine::Sparse => Box::new(Board::read(input)),
+        Engine::Bits => Box::new(BitBoard::read(input)),
+    };
 
-    for _ in 0..1000 {
+    // The elves can stop moving before round 10, so part two's answer is kept from then
+    let (mut part_a, mut part_b) = (0, None);
+    while part_b.is_none() || b.round() < 10 {
         b.step();
-        if b.finished {
-            break;
+        if b.round() == 10 {
+            part_a = b.score();
+        }
+        if b.finished() && part_b.is_none() {
+            part_b = Some(b.round());
+        }
+        if part_b.is_none() && b.round() >= limit {
+            return Err(format!("the elves were still moving after {limit} rounds"));
         }
     }
 
-    (format!("{part_a}"), format!("{}", b.round))
+    Ok((part_a, part_b.unwrap()))
+}
+
+pub fn day_23() -> (String, String) {
+    let f = read_to_string("input/day23.txt").unwrap();
+
+    let (part_a, part_b) = simulate(&f, Engine::Bits, MAX_ROUNDS).unwrap();
+
+    (format!("{part_a}"), format!("{part_b}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "....#..
+..###.#
+#...##.
+.#.#.##
+#.#.##.
+##.#.##
+.#..#..";
+
+    #[test]
+    fn engines_agree() {
+        assert_eq!(
+            simulate(EXAMPLE, Engine::Sparse, MAX_ROUNDS),
+            simulate(EXAMPLE, Engine::Bits, MAX_ROUNDS)
+        );
+
+        // Compare round by round on the real input, so a divergence is caught where it starts
+        let f = read_to_string("input/day23.txt").unwrap();
+        let mut sparse = Board::read(&f);
+        let mut bits = BitBoard::read(&f);
+
+        while !sparse.finished() {
+            sparse.step();
+            bits.step();
+            assert_eq!(sparse.score(), bits.score(), "round {}", sparse.round());
+            assert_eq!(
+                sparse.finished(),
+                bits.finished(),
+                "round {}",
+                sparse.round()
+            );
+        }
+
+        // Nobody to move, so it's over after the first round with no ground covered
+        for engine in [Engine::Sparse, Engine::Bits] {
+            assert_eq!(simulate("...\n...", engine, MAX_ROUNDS), Ok((0, 1)));
+            assert_eq!(simulate("", engine, MAX_ROUNDS), Ok((0, 1)));
+
+            // The example takes 15 rounds to settle
+            assert!(simulate(EXAMPLE, engine, 14).is_err());
+            assert_eq!(simulate(EXAMPLE, engine, 15), Ok((133, 15)));
+        }
+    }
 }
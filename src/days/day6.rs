@@ -1,35 +1,100 @@
-use std::fs::read_to_string;
+use std::fs::File;
+use std::io::{BufReader, Bytes, Read};
 
-pub fn day_6() -> (String, String) {
-    let f = read_to_string("input/day6.txt").unwrap();
+// Finds every position where the last `size` bytes are all different, counting positions as the
+// number of bytes read so far. Each byte's last position is kept in a table, so the start of the
+// distinct run only ever moves forward and the whole signal takes a single pass, whatever the
+// window size.
+struct Markers<I> {
+    bytes: I,
+    size: usize,
+    // One past the last position each byte was seen at, so 0 is never
+    last_seen: [usize; 256],
+    // Where the current run of distinct bytes starts
+    start: usize,
+    pos: usize,
+}
+
+impl<I: Iterator<Item = std::io::Result<u8>>> Markers<I> {
+    fn new(bytes: I, size: usize) -> Markers<I> {
+        Markers {
+            bytes,
+            size,
+            last_seen: [0; 256],
+            start: 0,
+            pos: 0,
+        }
+    }
+}
+
+// Markers in a signal that's already in memory
+#[allow(unused)]
+fn markers(signal: &[u8], size: usize) -> Markers<impl Iterator<Item = std::io::Result<u8>> + '_> {
+    Markers::new(signal.iter().map(|b| Ok(*b)), size)
+}
+
+// Markers in a signal read as it streams in
+fn read_markers<R: Read>(reader: R, size: usize) -> Markers<Bytes<BufReader<R>>> {
+    Markers::new(BufReader::new(reader).bytes(), size)
+}
+
+impl<I: Iterator<Item = std::io::Result<u8>>> Iterator for Markers<I> {
+    type Item = std::io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for b in self.bytes.by_ref() {
+            let b = match b {
+                Ok(b) => b as usize,
+                Err(e) => return Some(Err(e)),
+            };
 
-    let signal: Vec<char> = f.chars().collect();
+            self.start = self.start.max(self.last_seen[b]);
+            self.pos += 1;
+            self.last_seen[b] = self.pos;
 
-    let check = |pos, size| {
-        for x in 0..size {
-            for ax in (x + 1)..size {
-                if signal[x + pos] == signal[ax + pos] {
-                    return false;
-                }
+            if self.pos - self.start >= self.size {
+                return Some(Ok(self.pos));
             }
         }
-        true
+        None
+    }
+}
+
+pub fn day_6() -> (String, String) {
+    let first = |size| {
+        let f = File::open("input/day6.txt").unwrap();
+        let pos = read_markers(f, size).next().expect("no marker");
+        format!("{}", pos.unwrap())
     };
 
-    let mut packet_pos = None;
-    let mut message_pos = None;
+    (first(4), first(14))
+}
 
-    for x in 0..signal.len() {
-        if packet_pos.is_none() && check(x, 4) {
-            packet_pos = Some(x + 4);
-        }
-        if message_pos.is_none() && check(x, 14) {
-            message_pos = Some(x + 14);
-        }
-    }
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn markers_found() {
+        let first = |s: &str, size| markers(s.as_bytes(), size).next().unwrap().unwrap();
 
-    (
-        format!("{}", packet_pos.unwrap()),
-        format!("{}", message_pos.unwrap()),
-    )
+        assert_eq!(first("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), 7);
+        assert_eq!(first("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), 19);
+        assert_eq!(first("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4), 11);
+        assert_eq!(first("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14), 26);
+
+        let all = |s: &str, size| {
+            markers(s.as_bytes(), size)
+                .collect::<std::io::Result<Vec<usize>>>()
+                .unwrap()
+        };
+        assert_eq!(all("aabcbcda", 3), [4, 7, 8]);
+        assert_eq!(all("aaaa", 1), [1, 2, 3, 4]);
+        assert!(all("abc", 4).is_empty());
+
+        // Any byte counts, not just letters
+        let signal = [0u8, 255, 0, 128, 7, 255];
+        let streamed = read_markers(&signal[..], 3).map(|p| p.unwrap());
+        assert_eq!(streamed.collect::<Vec<usize>>(), [4, 5, 6]);
+    }
 }
@@ -1,3 +1,4 @@
+use std::fmt::Write;
 use std::fs::read_to_string;
 
 enum INode {
@@ -5,105 +6,462 @@ enum INode {
     Directory(String, Option<usize>, Vec<usize>),
 }
 
+const ROOT: usize = 0;
+
+// Nodes only ever point back at nodes added before them, which sizes relies on
 struct FileSystem {
     nodes: Vec<INode>,
 }
 
 impl FileSystem {
-    fn add_directory(&mut self, name: &str, parent: Option<usize>) -> usize {
-        let node = self.nodes.len();
-        self.nodes
-            .push(INode::Directory(name.to_string(), parent, vec![]));
-        if let Some(p) = parent {
-            if let INode::Directory(_, _, children) = &mut self.nodes[p] {
-                children.push(node)
+    fn new() -> FileSystem {
+        FileSystem {
+            nodes: vec![INode::Directory(String::new(), None, vec![])],
+        }
+    }
+
+    // Replays a terminal session. cd takes relative or absolute paths. Listing or visiting a
+    // directory twice finds the one that's already there, and listing a file twice keeps the
+    // latest size.
+    fn read(input: &str) -> Result<FileSystem, String> {
+        let mut fs = FileSystem::new();
+        let mut cwd = ROOT;
+
+        for (n, l) in input.lines().enumerate() {
+            let error = |e: &str| format!("line {}: {e}: {l:?}", n + 1);
+
+            if let Some(path) = l.strip_prefix("$ cd ") {
+                if path.starts_with('/') {
+                    cwd = ROOT;
+                }
+                for dn in path.split('/') {
+                    cwd = match dn {
+                        "" | "." => cwd,
+                        ".." => fs.parent(cwd).unwrap_or(ROOT),
+                        _ => fs.add_directory(dn, cwd).map_err(|e| error(&e))?,
+                    };
+                }
+            } else if l == "$ ls" || l.is_empty() {
+            } else if let Some(dn) = l.strip_prefix("dir ") {
+                fs.add_directory(dn, cwd).map_err(|e| error(&e))?;
+            } else if l.starts_with('$') {
+                return Err(error("unknown command"));
+            } else {
+                let (size, name) = l.split_once(' ').ok_or_else(|| error("not a file"))?;
+                let size = size.parse::<u32>().map_err(|_| error("bad file size"))?;
+                fs.add_file(name, size, cwd).map_err(|e| error(&e))?;
             }
         }
-        node
+
+        Ok(fs)
+    }
+
+    fn name(&self, node: usize) -> &str {
+        match &self.nodes[node] {
+            INode::File(name, _, _) | INode::Directory(name, _, _) => name,
+        }
+    }
+
+    fn parent(&self, node: usize) -> Option<usize> {
+        match &self.nodes[node] {
+            INode::File(_, parent, _) | INode::Directory(_, parent, _) => *parent,
+        }
+    }
+
+    fn children(&self, node: usize) -> &[usize] {
+        match &self.nodes[node] {
+            INode::File(..) => &[],
+            INode::Directory(_, _, children) => children,
+        }
+    }
+
+    fn is_directory(&self, node: usize) -> bool {
+        matches!(self.nodes[node], INode::Directory(..))
+    }
+
+    fn child(&self, node: usize, name: &str) -> Option<usize> {
+        let children = self.children(node).iter();
+        children.copied().find(|c| self.name(*c) == name)
+    }
+
+    fn attach(&mut self, node: INode, parent: usize) -> usize {
+        let n = self.nodes.len();
+        self.nodes.push(node);
+        if let INode::Directory(_, _, children) = &mut self.nodes[parent] {
+            children.push(n)
+        }
+        n
     }
-    fn add_file(&mut self, name: &str, size: u32, parent: Option<usize>) -> usize {
-        let node = self.nodes.len();
-        self.nodes.push(INode::File(name.to_string(), parent, size));
-        if let Some(p) = parent {
-            if let INode::Directory(_, _, children) = &mut self.nodes[p] {
-                children.push(node)
+
+    // Finds the directory if it's already there. A file can't share its name.
+    fn add_directory(&mut self, name: &str, parent: usize) -> Result<usize, String> {
+        match self.child(parent, name) {
+            Some(n) if self.is_directory(n) => Ok(n),
+            Some(_) => Err(format!("{name} is already a file")),
+            None => Ok(self.attach(
+                INode::Directory(name.to_string(), Some(parent), vec![]),
+                parent,
+            )),
+        }
+    }
+
+    // Updates the size if the file's already there. A directory can't share its name.
+    fn add_file(&mut self, name: &str, size: u32, parent: usize) -> Result<usize, String> {
+        let file = INode::File(name.to_string(), Some(parent), size);
+        match self.child(parent, name) {
+            Some(n) if self.is_directory(n) => Err(format!("{name} is already a directory")),
+            Some(n) => {
+                self.nodes[n] = file;
+                Ok(n)
             }
+            None => Ok(self.attach(file, parent)),
+        }
+    }
+
+    // Absolute, with a trailing slash on directories
+    fn path(&self, node: usize) -> String {
+        let mut names = vec![];
+        let mut n = node;
+        while let Some(p) = self.parent(n) {
+            names.push(self.name(n));
+            n = p;
+        }
+        names.reverse();
+
+        let mut path = format!("/{}", names.join("/"));
+        if self.is_directory(node) && node != ROOT {
+            path.push('/');
         }
-        node
+        path
     }
 
-    fn directory_size(&self, cached: &mut Vec<Option<u32>>, node: usize) -> u32 {
-        match (&self.nodes[node], cached[node]) {
-            (INode::File(_, _, size), _) => *size,
-            (_, Some(s)) => s,
-            (INode::Directory(_, _, children), _) => {
-                let mut size = 0;
+    // Takes absolute paths only. Empty parts and "." are skipped, ".." goes up.
+    fn resolve(&self, path: &str) -> Option<usize> {
+        let mut node = ROOT;
+        for part in path.strip_prefix('/')?.split('/') {
+            node = match part {
+                "" | "." => node,
+                ".." => self.parent(node).unwrap_or(ROOT),
+                _ => self.child(node, part)?,
+            };
+        }
+        Some(node)
+    }
+
+    // The size of every node, with directories holding everything under them. Children always
+    // come after their parents, so one backwards pass adds up every directory.
+    fn sizes(&self) -> Vec<u32> {
+        let mut sizes = vec![0; self.nodes.len()];
+        for n in (0..self.nodes.len()).rev() {
+            if let INode::File(_, _, size) = self.nodes[n] {
+                sizes[n] = size;
+            }
+            if let Some(p) = self.parent(n) {
+                sizes[p] += sizes[n];
+            }
+        }
+        sizes
+    }
+
+    #[allow(unused)]
+    fn du(&self, path: &str) -> Option<u32> {
+        self.resolve(path).map(|n| self.sizes()[n])
+    }
+
+    // Every file and directory at least `min_size` in size, as paths in the order they were found
+    #[allow(unused)]
+    fn find(&self, min_size: u32) -> Vec<(String, u32)> {
+        let sizes = self.sizes();
+        (0..self.nodes.len())
+            .filter(|n| sizes[*n] >= min_size)
+            .map(|n| (self.path(n), sizes[n]))
+            .collect()
+    }
+
+    // Drawn the way the puzzle does, with children sorted by name
+    #[allow(unused)]
+    fn tree(&self) -> String {
+        let mut out = String::new();
+        self.draw(ROOT, 0, &mut out);
+        out
+    }
+
+    fn draw(&self, node: usize, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        match &self.nodes[node] {
+            INode::File(name, _, size) => {
+                writeln!(out, "{indent}- {name} (file, size={size})").unwrap();
+            }
+            INode::Directory(name, _, children) => {
+                let name = if node == ROOT { "/" } else { name };
+                writeln!(out, "{indent}- {name} (dir)").unwrap();
+
+                let mut children = children.clone();
+                children.sort_by_key(|c| self.name(*c));
                 for c in children {
-                    size += self.directory_size(cached, *c);
+                    self.draw(c, depth + 1, out);
                 }
-                cached[node] = Some(size);
-                size
             }
         }
     }
 
-    fn scan_directories(&self) -> (u32, u32) {
-        let mut sizes: Vec<Option<u32>> = vec![None; self.nodes.len()];
-        let mut sum_of_moderate = 0;
-        let mut smallest_acceptable: Option<u32> = None;
+    // Directories in depth first order, each with the position just past everything under it
+    fn preorder(&self) -> Vec<(usize, usize)> {
+        let mut order = vec![];
+        let mut stack = vec![(ROOT, false)];
+        let mut open = vec![];
 
-        for n in 0..self.nodes.len() {
-            if let INode::Directory(_, _, _) = self.nodes[n] {
-                let size = self.directory_size(&mut sizes, n);
-                if size <= 100_000 {
-                    sum_of_moderate += size;
+        while let Some((node, done)) = stack.pop() {
+            if done {
+                let at: usize = open.pop().unwrap();
+                order[at] = (node, order.len());
+                continue;
+            }
+
+            open.push(order.len());
+            order.push((node, 0));
+            stack.push((node, true));
+            for c in self.children(node).iter().rev() {
+                if self.is_directory(*c) {
+                    stack.push((*c, false));
                 }
+            }
+        }
+        order
+    }
+
+    // How much more space has to be freed to leave `required` free on a disk of `total`
+    fn shortfall(&self, sizes: &[u32], disk: &Disk) -> Option<u32> {
+        let free = disk.total.checked_sub(sizes[ROOT])?;
+        Some(disk.required.saturating_sub(free))
+    }
+
+    fn cleanup(&self, nodes: Vec<usize>, sizes: &[u32]) -> Cleanup {
+        Cleanup {
+            freed: nodes.iter().map(|n| sizes[*n]).sum(),
+            paths: nodes.iter().map(|n| self.path(*n)).collect(),
+        }
+    }
+
+    // The smallest directory that frees enough on its own
+    fn smallest_directory(&self, need: u32, sizes: &[u32]) -> Option<usize> {
+        (0..self.nodes.len())
+            .filter(|n| self.is_directory(*n) && sizes[*n] >= need)
+            .min_by_key(|n| sizes[*n])
+    }
+
+    fn scan_directories(&self, threshold: u32, disk: &Disk) -> (u32, u32) {
+        let sizes = self.sizes();
+        let sum_of_moderate = (0..self.nodes.len())
+            .filter(|n| self.is_directory(*n) && sizes[*n] <= threshold)
+            .map(|n| sizes[n])
+            .sum();
+
+        let need = self.shortfall(&sizes, disk).expect("the disk is overfull");
+        let smallest = match need {
+            0 => 0,
+            _ => sizes[self.smallest_directory(need, &sizes).unwrap()],
+        };
+        (sum_of_moderate, smallest)
+    }
+
+    // Deleting a single directory. None if the disk is overfull, or nothing is big enough.
+    #[allow(unused)]
+    fn plan_cleanup(&self, disk: &Disk) -> Option<Cleanup> {
+        let sizes = self.sizes();
+        let deleted = match self.shortfall(&sizes, disk)? {
+            0 => vec![],
+            need => vec![self.smallest_directory(need, &sizes)?],
+        };
+        Some(self.cleanup(deleted, &sizes))
+    }
 
-                let free_space = 70_000_000 - sizes[0].unwrap();
-                if free_space + size >= 30_000_000 {
-                    if let Some(s) = smallest_acceptable {
-                        smallest_acceptable = Some(s.min(size));
-                    } else {
-                        smallest_acceptable = Some(size);
-                    }
+    // Deleting any number of directories, none inside another. This can free less than
+    // plan_cleanup, but is far more expensive, so it's only worked out when asked for.
+    #[allow(unused)]
+    fn plan_cleanup_several(&self, disk: &Disk) -> Option<Cleanup> {
+        let sizes = self.sizes();
+        let deleted = match self.shortfall(&sizes, disk)? {
+            0 => vec![],
+            need => self.smallest_deletions(need, &sizes)?,
+        };
+        Some(self.cleanup(deleted, &sizes))
+    }
+
+    // The cheapest set of directories, none inside another, that adds up to at least `need`.
+    // Walking the directories depth first, each one is either deleted, which skips everything
+    // under it, or kept. Every reachable total below `need` is a bit in a set per position, so
+    // this costs a bit per byte of the shortfall for each directory, but is never exponential.
+    fn smallest_deletions(&self, need: u32, sizes: &[u32]) -> Option<Vec<usize>> {
+        let order = self.preorder();
+        let words = (need as usize).div_ceil(64);
+        let has = |set: &[u64], s: u32| (set[s as usize / 64] >> (s % 64)) & 1 == 1;
+
+        let mut reach = vec![vec![0u64; words]; order.len() + 1];
+        reach[0][0] = 1;
+
+        // The total, and where the last directory deleted to reach it was
+        let mut best: Option<(u32, usize)> = None;
+
+        for (i, &(node, end)) in order.iter().enumerate() {
+            let size = sizes[node];
+            let (before, after) = reach.split_at_mut(i + 1);
+            let here = &before[i];
+
+            // The smallest total here that deleting this directory takes to `need` or beyond
+            let lowest = need.saturating_sub(size);
+            if let Some(s) = first_set(here, lowest).filter(|s| *s < need) {
+                if best.is_none_or(|(b, _)| s + size < b) {
+                    best = Some((s + size, i));
                 }
             }
+
+            for (w, bits) in here.iter().enumerate() {
+                after[0][w] |= bits;
+            }
+            shift_or(&mut after[end - i - 1], here, size);
+        }
+
+        // Walk back from the best total, preferring to have kept each directory
+        let (mut total, mut at) = best?;
+        let mut deleted = vec![order[at].0];
+        total -= sizes[order[at].0];
+
+        while total > 0 {
+            if has(&reach[at - 1], total) {
+                at -= 1;
+                continue;
+            }
+            let (j, &(node, _)) = order
+                .iter()
+                .enumerate()
+                .find(|(j, (n, end))| {
+                    *end == at && sizes[*n] <= total && has(&reach[*j], total - sizes[*n])
+                })
+                .unwrap();
+            deleted.push(node);
+            total -= sizes[node];
+            at = j;
         }
 
-        (sum_of_moderate, smallest_acceptable.unwrap())
+        deleted.sort();
+        Some(deleted)
+    }
+}
+
+// The lowest bit set at or above `from`
+fn first_set(set: &[u64], from: u32) -> Option<u32> {
+    let start = from as usize / 64;
+    let masked = set.get(start)? & (u64::MAX << (from % 64));
+    let (w, bits) = std::iter::once((start, masked))
+        .chain(set.iter().copied().enumerate().skip(start + 1))
+        .find(|(_, bits)| *bits != 0)?;
+    Some(w as u32 * 64 + bits.trailing_zeros())
+}
+
+// Sets every bit of `src`, moved up by `by`, in `dst`. Anything pushed past the end is dropped.
+fn shift_or(dst: &mut [u64], src: &[u64], by: u32) {
+    let (words, bits) = (by as usize / 64, by % 64);
+    for w in (words..dst.len()).rev() {
+        let mut v = src[w - words] << bits;
+        if bits > 0 && w > words {
+            v |= src[w - words - 1] >> (64 - bits);
+        }
+        dst[w] |= v;
     }
 }
 
+struct Disk {
+    total: u32,
+    required: u32,
+}
+
+const DEVICE: Disk = Disk {
+    total: 70_000_000,
+    required: 30_000_000,
+};
+
+// Directories to delete, as paths, and how much deleting them frees
+#[derive(Debug, PartialEq)]
+struct Cleanup {
+    freed: u32,
+    paths: Vec<String>,
+}
+
 pub fn day_7() -> (String, String) {
     let f = read_to_string("input/day7.txt").unwrap();
 
-    let mut fs = FileSystem { nodes: vec![] };
-    let mut nodes = vec![];
+    let fs = FileSystem::read(&f).unwrap_or_else(|e| panic!("day 7: {e}"));
 
-    for l in f.lines() {
-        if l.starts_with("$ cd") {
-            let (_, dn) = l.split_at(5);
+    let (a, b) = fs.scan_directories(100_000, &DEVICE);
+    (format!("{}", a), format!("{}", b))
+}
 
-            let node = if dn == "/" {
-                Some(fs.add_directory("", None))
-            } else if dn == ".." {
-                nodes.pop();
-                None
-            } else {
-                Some(fs.add_directory("dn", Some(*nodes.last().unwrap())))
-            };
+#[cfg(test)]
+mod test {
+    use super::*;
 
-            if let Some(n) = node {
-                nodes.push(n);
-            }
-        } else if l.starts_with('$') || l.starts_with("dir") {
-        } else if !l.is_empty() {
-            let (size, name) = l.split_once(' ').unwrap();
-            let sizei = str::parse::<u32>(size).unwrap();
-            fs.add_file(name, sizei, Some(*nodes.last().unwrap()));
-        }
+    const EXAMPLE: &str =
+        "$ cd /\n$ ls\ndir a\n14848514 b.txt\n8504156 c.dat\ndir d\n$ cd a\n$ ls\n\
+        dir e\n29116 f\n2557 g\n62596 h.lst\n$ cd e\n$ ls\n584 i\n$ cd ..\n$ cd ..\n$ cd d\n$ ls\n\
+        4060174 j\n8033020 d.log\n5626152 d.ext\n7214296 k\n";
+
+    #[test]
+    fn file_system() {
+        // Going back over a directory shouldn't duplicate anything
+        let revisited = format!("{EXAMPLE}$ cd /a\n$ ls\ndir e\n29116 f\n$ cd e\n$ ls\n584 i\n");
+        let fs = FileSystem::read(&revisited).unwrap();
+
+        assert_eq!(fs.nodes.len(), 14);
+        assert_eq!(fs.du("/"), Some(48381165));
+        assert_eq!(fs.du("/a/"), Some(94853));
+        assert_eq!(fs.du("/a/e/../../d/j"), Some(4060174));
+        assert_eq!(fs.du("/x"), None);
+        assert_eq!(fs.path(fs.resolve("/a/e").unwrap()), "/a/e/");
+
+        let found = fs.find(24_000_000);
+        assert_eq!(
+            found,
+            [("/".to_string(), 48381165), ("/d/".to_string(), 24933642)]
+        );
+
+        let tree = fs.tree();
+        assert!(
+            tree.starts_with("- / (dir)\n  - a (dir)\n    - e (dir)\n      - i (file, size=584)\n")
+        );
+        assert!(tree.ends_with("    - k (file, size=7214296)\n"));
+
+        assert_eq!(fs.scan_directories(100_000, &DEVICE), (95437, 24933642));
+        assert!(FileSystem::read("$ rm -rf /\n").is_err());
+
+        let clash = FileSystem::read("$ ls\n10 a\ndir a\n").err();
+        assert_eq!(clash.unwrap(), "line 3: a is already a file: \"dir a\"");
+        assert!(FileSystem::read("$ ls\ndir a\n10 a\n").is_err());
     }
 
-    let (a, b) = fs.scan_directories();
-    (format!("{}", a), format!("{}", b))
+    #[test]
+    fn cleanup() {
+        let fs = FileSystem::read(EXAMPLE).unwrap();
+        let single = fs.plan_cleanup(&DEVICE).unwrap();
+        assert_eq!(single.paths, ["/d/"]);
+        assert_eq!(fs.plan_cleanup_several(&DEVICE), Some(single));
+
+        // Two small directories beat the one big one
+        let input = "$ cd /x\n$ ls\n6 f\n$ cd /y\n$ ls\n5 g\n$ cd /z\n$ ls\n20 h\n$ cd w\n";
+        let fs = FileSystem::read(input).unwrap();
+        let disk = |total, required| Disk { total, required };
+
+        let single = fs.plan_cleanup(&disk(40, 20)).unwrap();
+        assert_eq!((single.freed, single.paths), (20, vec!["/z/".into()]));
+        let several = fs.plan_cleanup_several(&disk(40, 20)).unwrap();
+        assert_eq!(
+            (several.freed, several.paths),
+            (11, vec!["/x/".into(), "/y/".into()])
+        );
+
+        assert_eq!(fs.plan_cleanup_several(&disk(40, 5)).unwrap().freed, 0);
+        assert_eq!(fs.plan_cleanup_several(&disk(40, 40)).unwrap().paths, ["/"]);
+        assert!(fs.plan_cleanup(&disk(30, 0)).is_none());
+        assert!(fs.plan_cleanup_several(&disk(40, 41)).is_none());
+    }
 }
@@ -1,3 +1,4 @@
+use crate::utils::grid::Grid;
 use std::fs::read_to_string;
 
 #[derive(Debug)]
@@ -6,112 +7,40 @@ struct Square {
     visible: bool,
 }
 
-struct GridLine {
-    squares: Vec<Square>,
-}
-
-struct Grid {
-    lines: Vec<GridLine>,
-}
-
-impl Grid {
-    fn width(&self) -> isize {
-        self.lines[0].squares.len() as isize
-    }
-    fn height(&self) -> isize {
-        self.lines.len() as isize
-    }
-    fn get(&mut self, x: isize, y: isize) -> Option<&mut Square> {
-        if y < 0 || y >= self.lines.len() as isize {
-            None
-        } else {
-            let line = &mut self.lines[y as usize];
-            if x < 0 || x >= line.squares.len() as isize {
-                None
-            } else {
-                Some(&mut line.squares[x as usize])
-            }
-        }
+// Marks the trees visible looking along a line of sight, from the first tree towards the last
+fn sight_line<'a>(squares: impl Iterator<Item = &'a mut Square>) {
+    let mut highest = -1;
+    for square in squares {
+        square.visible |= square.height > highest;
+        highest = highest.max(square.height);
     }
 }
+
 pub fn day_8() -> (String, String) {
     let f = read_to_string("input/day8.txt").unwrap();
 
-    let mut grid = Grid { lines: vec![] };
-
-    for l in f.lines() {
-        let mut squares = vec![];
-
-        for c in l.chars() {
-            let height = if c == '0' {
-                0
-            } else {
-                ((c as isize) - ('1' as isize)) + 1
-            };
-
-            squares.push(Square {
-                height,
-                visible: false,
-            });
-        }
-
-        grid.lines.push(GridLine { squares });
-    }
-
-    for y in 0..grid.lines.len() {
-        {
-            let mut highest = -1;
-            for x in 0..grid.lines[y].squares.len() {
-                let square = &mut grid.lines[y].squares[x];
-                square.visible |= square.height > highest;
-                highest = highest.max(square.height);
-            }
-        }
+    let mut grid = Grid::parse(&f, |c| Square {
+        height: c.to_digit(10).unwrap() as isize,
+        visible: false,
+    });
 
-        {
-            let mut highest = -1;
-            for x in (0..grid.lines[y].squares.len()).rev() {
-                let square = &mut grid.lines[y].squares[x];
-                square.visible |= square.height > highest;
-                highest = highest.max(square.height);
-            }
-        }
+    for y in 0..grid.height() {
+        sight_line(grid.row_mut(y).iter_mut());
+        sight_line(grid.row_mut(y).iter_mut().rev());
     }
 
-    for x in 0..grid.lines[0].squares.len() {
-        {
-            let mut highest = -1;
-            for y in 0..grid.lines.len() {
-                let square = &mut grid.lines[y].squares[x];
-                square.visible |= square.height > highest;
-                highest = highest.max(square.height);
-            }
-        }
-
-        {
-            let mut highest = -1;
-            for y in (0..grid.lines.len()).rev() {
-                let square = &mut grid.lines[y].squares[x];
-                square.visible |= square.height > highest;
-                highest = highest.max(square.height);
-            }
-        }
+    for x in 0..grid.width() {
+        sight_line(grid.column_mut(x));
+        sight_line(grid.column_mut(x).rev());
     }
 
-    let mut visible = 0;
-    for y in 0..grid.lines.len() {
-        for x in 0..grid.lines[y].squares.len() {
-            if grid.lines[y].squares[x].visible {
-                visible += 1;
-            }
-        }
-    }
+    let visible = grid.iter().filter(|(_, s)| s.visible).count();
 
     let dirs = vec![[-1, 0], [1, 0], [0, -1], [0, 1]];
 
     let mut highest_score: Option<i32> = None;
-    for x in 0..grid.width() {
-        for y in 0..grid.height() {
+    for x in 0..grid.width() as isize {
+        for y in 0..grid.height() as isize {
             let height = grid.get(x, y).unwrap().height;
 
             let mut score = 1;
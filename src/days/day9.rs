@@ -1,35 +1,25 @@
+use crate::utils::geom::{Dir4, Point2};
+use crate::utils::viz::{Frame, Visualize, BLACK, GREY, RED, WHITE};
 use std::collections::HashSet;
 use std::fs::read_to_string;
 
 struct RopeSim {
-    knots: Vec<[isize; 2]>,
-    visited: HashSet<[isize; 2]>,
+    knots: Vec<Point2>,
+    visited: HashSet<Point2>,
 }
 
 impl RopeSim {
-    fn move_head(&mut self, dir: [isize; 2], mag: usize) {
+    fn move_head(&mut self, dir: Dir4, mag: usize) {
         for _ in 0..mag {
-            self.knots[0][0] += dir[0];
-            self.knots[0][1] += dir[1];
+            self.knots[0] += dir.delta();
 
             for k in 0..self.knots.len() - 1 {
-                let head = &self.knots[k];
-                let tail = &self.knots[k + 1];
-
-                let diffx = head[0] - tail[0];
-                let diffy = head[1] - tail[1];
-
-                if head[0] == tail[0] {
-                    if diffy.abs() == 2 {
-                        self.knots[k + 1][1] += diffy.clamp(-1, 1)
-                    }
-                } else if head[1] == tail[1] {
-                    if diffx.abs() == 2 {
-                        self.knots[k + 1][0] += diffx.clamp(-1, 1)
-                    }
-                } else if diffx.abs() == 2 || diffy.abs() == 2 {
-                    self.knots[k + 1][0] += diffx.clamp(-1, 1);
-                    self.knots[k + 1][1] += diffy.clamp(-1, 1);
+                let diff = self.knots[k] - self.knots[k + 1];
+
+                // A knot only moves once it's no longer touching the one ahead of it, and then
+                // always moves a single step (diagonally if need be) towards it
+                if diff.chebyshev(Point2::ORIGIN) == 2 {
+                    self.knots[k + 1] += diff.signum();
                 }
             }
 
@@ -38,40 +28,99 @@ impl RopeSim {
     }
 }
 
+// The rope drawn within fixed bounds, so frames line up as it wanders around
+struct RopeView<'a> {
+    sim: &'a RopeSim,
+    min: Point2,
+    max: Point2,
+}
+
+impl Visualize for RopeView<'_> {
+    fn frame(&self) -> Frame {
+        let size = self.max - self.min;
+        let mut frame = Frame::new(size.x as usize + 1, size.y as usize + 1, BLACK);
+        let mut plot = |p: Point2, c| {
+            let p = p - self.min;
+            frame.set(p.x as usize, p.y as usize, c);
+        };
+
+        for p in &self.sim.visited {
+            plot(*p, GREY);
+        }
+        for p in self.sim.knots.iter().rev() {
+            plot(*p, WHITE);
+        }
+        plot(self.sim.knots[0], RED);
+        frame
+    }
+}
+
+fn read_moves(input: &str) -> Vec<(Dir4, usize)> {
+    let mut out = vec![];
+
+    for l in input.lines() {
+        let (dir_str, mag_str) = l.split_once(' ').unwrap();
+
+        let dir = match dir_str {
+            "U" => Dir4::North,
+            "D" => Dir4::South,
+            "L" => Dir4::West,
+            "R" => Dir4::East,
+            _ => todo!(),
+        };
+
+        let mag = str::parse::<usize>(mag_str).unwrap();
+        out.push((dir, mag));
+    }
+
+    out
+}
+
+// One frame per line of input, following the ten knot rope
+pub fn visualize(frames: &mut dyn FnMut(&dyn Visualize)) {
+    let f = read_to_string("input/day9.txt").unwrap();
+    let moves = read_moves(&f);
+
+    // The tail never gets anywhere the head hasn't been, so the head's range bounds everything
+    let (mut min, mut max, mut head) = (Point2::ORIGIN, Point2::ORIGIN, Point2::ORIGIN);
+    for (dir, mag) in &moves {
+        head += dir.delta() * *mag as isize;
+        min = Point2::new(min.x.min(head.x), min.y.min(head.y));
+        max = Point2::new(max.x.max(head.x), max.y.max(head.y));
+    }
+
+    let mut sim = RopeSim {
+        knots: vec![Point2::ORIGIN; 10],
+        visited: HashSet::from([Point2::ORIGIN]),
+    };
+
+    for (dir, mag) in moves {
+        sim.move_head(dir, mag);
+        frames(&RopeView {
+            sim: &sim,
+            min,
+            max,
+        });
+    }
+}
+
 pub fn day_9() -> (String, String) {
     let f = read_to_string("input/day9.txt").unwrap();
 
     let mut sim = RopeSim {
-        knots: vec![[0, 0], [0, 0]],
+        knots: vec![Point2::ORIGIN; 2],
         visited: HashSet::new(),
     };
 
     let mut long_sim = RopeSim {
-        knots: vec![[0, 0]; 10],
+        knots: vec![Point2::ORIGIN; 10],
         visited: HashSet::new(),
     };
 
-    long_sim.visited.insert([0, 0]);
-    sim.visited.insert([0, 0]);
-
-    for l in f.lines() {
-        let (dir_str, mag_str) = l.split_once(' ').unwrap();
-
-        // I hope this is code motioned out...
-        let dir = if dir_str == "U" {
-            [0, -1]
-        } else if dir_str == "D" {
-            [0, 1]
-        } else if dir_str == "L" {
-            [-1, 0]
-        } else if dir_str == "R" {
-            [1, 0]
-        } else {
-            todo!()
-        };
-
-        let mag = str::parse::<usize>(mag_str).unwrap();
+    long_sim.visited.insert(Point2::ORIGIN);
+    sim.visited.insert(Point2::ORIGIN);
 
+    for (dir, mag) in read_moves(&f) {
         sim.move_head(dir, mag);
         long_sim.move_head(dir, mag);
     }
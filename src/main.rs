@@ -24,12 +24,186 @@ use crate::days::day7::day_7;
 use crate::days::day8::day_8;
 use crate::days::day9::day_9;
 
-use std::time;
+use crate::utils::viz::{Exporter, Format, Terminal, Visualize};
+use std::path::PathBuf;
+use std::{env, process, time};
 
 mod days;
 mod utils;
 
+type VisualizeFn = fn(&mut dyn FnMut(&dyn Visualize));
+
+const USAGE: &str = "usage: aoc22 [--strict]
+       aoc22 viz <day> --out <dir> [--gif] [--scale n] [--every n]
+       aoc22 animate <day> [--fps n] [--every n] [--steps n] [--width n] [--height n]
+       aoc22 strategy [--rounds n]
+       aoc22 elves --above <calories>
+       aoc22 crates --crane <name> [--steps n]";
+
+fn visualizer(day: Option<&String>) -> Result<VisualizeFn, String> {
+    let day = day.ok_or(USAGE)?;
+
+    Ok(match day.as_str() {
+        "9" => days::day9::visualize,
+        "14" => days::day14::visualize,
+        "17" => days::day17::visualize,
+        "23" => days::day23::visualize,
+        "24" => days::day24::visualize,
+        _ => {
+            return Err(format!(
+                "day {day} can't be visualized, try 9, 14, 17, 23 or 24"
+            ))
+        }
+    })
+}
+
+fn number(arg: &str, value: Option<&String>) -> Result<usize, String> {
+    value
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .ok_or(format!("{arg} needs a positive number"))
+}
+
+// Feeds every frame to a sink that can fail. Frames keep coming after a failure, but only the
+// first error is reported.
+fn play(
+    run: VisualizeFn,
+    mut sink: impl FnMut(&dyn Visualize) -> std::io::Result<()>,
+) -> Result<(), String> {
+    let mut error = None;
+    run(&mut |v| {
+        if error.is_none() {
+            error = sink(v).err();
+        }
+    });
+    error.map_or(Ok(()), |e| Err(e.to_string()))
+}
+
+// Exports frames of one of the simulation days, either as numbered PPMs or a single GIF
+fn viz(args: &[String]) -> Result<(), String> {
+    let mut args = args.iter();
+    let run = visualizer(args.next())?;
+
+    let (mut out, mut format, mut scale, mut every) = (None, Format::Ppm, 1, 1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--gif" => format = Format::Gif,
+            "--scale" => scale = number(arg, args.next())?,
+            "--every" => every = number(arg, args.next())?,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let out = out.ok_or(USAGE)?;
+    let mut exporter = Exporter::new(&out, format, scale, every).map_err(|e| e.to_string())?;
+    play(run, |v| exporter.push(v))?;
+
+    let count = exporter.finish().map_err(|e| e.to_string())?;
+    println!("Wrote {count} frames to {}", out.display());
+    Ok(())
+}
+
+// Plays one of the simulation days in the terminal, as it runs
+fn animate(args: &[String]) -> Result<(), String> {
+    let mut args = args.iter();
+    let run = visualizer(args.next())?;
+
+    let (mut fps, mut every, mut steps) = (30, 1, None);
+    let (mut width, mut height) = (160, 96);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fps" => fps = number(arg, args.next())?,
+            "--every" => every = number(arg, args.next())?,
+            "--steps" => steps = Some(number(arg, args.next())?),
+            "--width" => width = number(arg, args.next())?,
+            "--height" => height = number(arg, args.next())?,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let mut terminal =
+        Terminal::new(fps, every, steps, (width, height)).map_err(|e| e.to_string())?;
+    let result = play(run, |v| terminal.push(v));
+
+    // Restore the cursor even if drawing failed part way
+    terminal.finish().map_err(|e| e.to_string())?;
+    result
+}
+
+// Day 2's strategy guide, read every way it could be meant
+fn strategy(args: &[String]) -> Result<(), String> {
+    let rounds = match args {
+        [] => None,
+        [arg, n] if arg == "--rounds" => Some(number(arg, Some(n))?),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    println!("{}", days::day2::strategies(rounds)?);
+    Ok(())
+}
+
+// Day 1's elves carrying more than some number of calories, listed as they're read
+fn elves(args: &[String]) -> Result<(), String> {
+    let threshold = match args {
+        [arg, n] if arg == "--above" => n.parse::<u64>().map_err(|_| USAGE)?,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut found = 0;
+    let count = days::day1::elves_above(threshold, |index, total| {
+        println!("elf {index}: {total}");
+        found += 1;
+    })
+    .map_err(|e| e.to_string())?;
+
+    println!("{found} of {count} elves carry more than {threshold} calories");
+    Ok(())
+}
+
+// Day 5's rearrangement done by any of the cranes, drawn part way through or at the end
+fn crates(args: &[String]) -> Result<(), String> {
+    let mut args = args.iter();
+
+    let (mut crane, mut steps) = (None, None);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--crane" => crane = Some(args.next().ok_or(USAGE)?),
+            "--steps" => steps = Some(number(arg, args.next())?),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let crane = crane.ok_or(USAGE)?;
+    println!("{}", days::day5::rearrange(crane, steps)?);
+    Ok(())
+}
+
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let strict = args == ["--strict"];
+    if !args.is_empty() && !strict {
+        let result = match args[0].as_str() {
+            "viz" => viz(&args[1..]),
+            "animate" => animate(&args[1..]),
+            "strategy" => strategy(&args[1..]),
+            "elves" => elves(&args[1..]),
+            "crates" => crates(&args[1..]),
+            _ => Err(USAGE.to_string()),
+        };
+        if let Err(e) = result {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
+
+    // Problems with the input are looked for once, rather than on every timed run
+    if let Err(e) = days::day3::check(strict) {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
     let iters = 10;
 
     for i in 0..iters {
@@ -6,47 +6,57 @@
 // so it's probably not an actual win. If I did it again, I'd just make it copy, even if high
 // performance was the goal.
 
-// Also there are known bugs. But see above. Don't do this.
-
-type BitWidth = u128;
-const WIDTH: isize = 128;
-const WIDTH_U: usize = 128;
-
-#[derive(Clone)]
-pub struct BitGrid {
-    backing: Vec<Vec<BitWidth>>,
+// There used to be known bugs too, mostly with views reaching more than a word left of the
+// grid. Those are fixed, and the views are now checked against working things out a bit at a
+// time. Everything added since returns a new grid, and is built from shifted copies rather than
+// chains of views. The only view it uses is a single Window, to line rows back up with the
+// word boundaries after a flip, crop or resize.
+
+// The word size is a type parameter. BitGrid on its own means u128 words, and BitGrid::new
+// makes one of those, in the same way HashMap::new picks the default hasher.
+
+use super::viz::{Frame, Visualize, BLACK, WHITE};
+use super::word::Word;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct BitGrid<W: Word = u128> {
+    // Rows are stored one after another, each padded out to a whole number of words
+    backing: Vec<W>,
     width: usize,
     height: usize,
 }
 
 pub trait BitView {
-    fn get_backing(&self, x: isize, y: isize) -> BitWidth;
+    type Word: Word;
+
+    fn get_backing(&self, x: isize, y: isize) -> Self::Word;
     fn width(&self) -> usize;
     fn height(&self) -> usize;
 }
 
-impl BitView for BitGrid {
-    fn get_backing(&self, x: isize, y: isize) -> BitWidth {
+impl<W: Word> BitView for BitGrid<W> {
+    type Word = W;
+
+    fn get_backing(&self, x: isize, y: isize) -> W {
         let get_backing = |xi: isize| {
-            *self
-                .backing
-                .get(y as usize)
-                .map(|v| v.get(xi as usize).unwrap_or(&0))
-                .unwrap_or(&0)
+            if y < 0 || y >= self.height as isize || xi < 0 || xi >= self.words() as isize {
+                W::ZERO
+            } else {
+                self.row(y as usize)[xi as usize]
+            }
         };
 
-        let rem = x.rem_euclid(WIDTH);
-
-        let prev = get_backing((x + WIDTH) / WIDTH - 1);
-        let this = get_backing((x + WIDTH) / WIDTH);
+        let width = W::BITS as isize;
+        let rem = x.rem_euclid(width) as u32;
 
-        let prev_bits = prev.overflowing_shr(rem as u32).0;
-        let this_bits = match this.overflowing_shl((WIDTH - rem) as u32) {
-            (_, true) => 0,
-            (v, _) => v,
-        };
+        let prev = get_backing(x.div_euclid(width));
+        let this = get_backing(x.div_euclid(width) + 1);
 
-        prev_bits | this_bits
+        if rem == 0 {
+            prev
+        } else {
+            prev >> rem | this << (W::BITS as u32 - rem)
+        }
     }
 
     fn width(&self) -> usize {
@@ -88,15 +98,13 @@ impl<T> BitView for Window<'_, T>
 where
     T: BitView,
 {
-    fn get_backing(&self, x: isize, y: isize) -> BitWidth {
+    type Word = T::Word;
+
+    fn get_backing(&self, x: isize, y: isize) -> T::Word {
         if y < 0 || y >= self.height() as isize {
-            return 0;
+            return T::Word::ZERO;
         }
-        let bit_count = self.width - x;
-        let mask = match (1 as BitWidth).overflowing_shl(bit_count as u32) {
-            (v, false) => v - 1,
-            _ => !0,
-        };
+        let mask = T::Word::low_mask(self.width - x);
         self.view.get_backing(x + self.x, y + self.y) & mask
     }
 
@@ -131,15 +139,13 @@ impl<T> BitView for Shifted<'_, T>
 where
     T: BitView,
 {
-    fn get_backing(&self, x: isize, y: isize) -> BitWidth {
+    type Word = T::Word;
+
+    fn get_backing(&self, x: isize, y: isize) -> T::Word {
         if y < 0 || y >= self.height() as isize {
-            return 0;
+            return T::Word::ZERO;
         }
-        let bit_count = self.view.width() as isize - x;
-        let mask = match (1 as BitWidth).overflowing_shl(bit_count as u32) {
-            (v, false) => v - 1,
-            _ => !0,
-        };
+        let mask = T::Word::low_mask(self.view.width() as isize - x);
         self.view.get_backing(x - self.x, y - self.y) & mask
     }
 
@@ -174,9 +180,11 @@ impl<T> BitView for ShiftedWrap<'_, T>
 where
     T: BitView,
 {
-    fn get_backing(&self, x: isize, y: isize) -> BitWidth {
+    type Word = T::Word;
+
+    fn get_backing(&self, x: isize, y: isize) -> T::Word {
         if y < 0 || y >= self.height() as isize {
-            return 0;
+            return T::Word::ZERO;
         }
         let width = self.view.width() as isize;
         let height = self.view.height() as isize;
@@ -195,22 +203,24 @@ where
     }
 }
 
+type PrimFn<W> = fn(W, W) -> W;
+
 pub struct Prim<'grid, Ta, Tb>
 where
     Ta: BitView,
-    Tb: BitView,
+    Tb: BitView<Word = Ta::Word>,
 {
     a: &'grid Ta,
     b: &'grid Tb,
-    prim: fn(BitWidth, BitWidth) -> BitWidth,
+    prim: PrimFn<Ta::Word>,
 }
 
 impl<'grid, Ta, Tb> Prim<'grid, Ta, Tb>
 where
     Ta: BitView,
-    Tb: BitView,
+    Tb: BitView<Word = Ta::Word>,
 {
-    pub fn new(a: &'grid Ta, b: &'grid Tb, prim: fn(BitWidth, BitWidth) -> BitWidth) -> Self {
+    pub fn new(a: &'grid Ta, b: &'grid Tb, prim: PrimFn<Ta::Word>) -> Self {
         Prim { a, b, prim }
     }
 }
@@ -218,9 +228,11 @@ where
 impl<Ta, Tb> BitView for Prim<'_, Ta, Tb>
 where
     Ta: BitView,
-    Tb: BitView,
+    Tb: BitView<Word = Ta::Word>,
 {
-    fn get_backing(&self, x: isize, y: isize) -> BitWidth {
+    type Word = Ta::Word;
+
+    fn get_backing(&self, x: isize, y: isize) -> Ta::Word {
         (self.prim)(self.a.get_backing(x, y), self.b.get_backing(x, y))
     }
 
@@ -236,7 +248,7 @@ where
 pub struct Or<'grid, Ta, Tb>
 where
     Ta: BitView,
-    Tb: BitView,
+    Tb: BitView<Word = Ta::Word>,
 {
     a: &'grid Ta,
     b: &'grid Tb,
@@ -245,8 +257,10 @@ where
 impl<'grid, Ta, Tb> Or<'grid, Ta, Tb>
 where
     Ta: BitView,
-    Tb: BitView,
+    Tb: BitView<Word = Ta::Word>,
 {
+    // Only the visual tests use this now
+    #[allow(unused)]
     pub fn new(a: &'grid Ta, b: &'grid Tb) -> Self {
         Or { a, b }
     }
@@ -255,9 +269,11 @@ where
 impl<Ta, Tb> BitView for Or<'_, Ta, Tb>
 where
     Ta: BitView,
-    Tb: BitView,
+    Tb: BitView<Word = Ta::Word>,
 {
-    fn get_backing(&self, x: isize, y: isize) -> BitWidth {
+    type Word = Ta::Word;
+
+    fn get_backing(&self, x: isize, y: isize) -> Ta::Word {
         self.a.get_backing(x, y) | self.b.get_backing(x, y)
     }
 
@@ -272,41 +288,128 @@ where
 
 impl BitGrid {
     pub fn new(width: usize, height: usize) -> BitGrid {
+        BitGrid::empty(width, height)
+    }
+}
+
+impl<W: Word> BitGrid<W> {
+    pub fn empty(width: usize, height: usize) -> BitGrid<W> {
         BitGrid {
-            backing: vec![vec![0; (width + WIDTH_U - 1) / WIDTH_U]; height],
+            backing: vec![W::ZERO; width.div_ceil(W::BITS) * height],
             width,
             height,
         }
     }
 
+    fn words(&self) -> usize {
+        self.width.div_ceil(W::BITS)
+    }
+
+    fn row(&self, y: usize) -> &[W] {
+        let words = self.words();
+        &self.backing[y * words..(y + 1) * words]
+    }
+
+    fn row_mut(&mut self, y: usize) -> &mut [W] {
+        let words = self.words();
+        &mut self.backing[y * words..(y + 1) * words]
+    }
+
+    fn rows(&self) -> impl DoubleEndedIterator<Item = &[W]> + ExactSizeIterator {
+        (0..self.height).map(|y| self.row(y))
+    }
+
     pub fn get(&self, x: usize, y: usize) -> bool {
-        (self.backing[y][x / WIDTH_U] >> (x % WIDTH_U) & 1) == 1
+        (self.row(y)[x / W::BITS] >> (x % W::BITS) as u32 & W::ONE) == W::ONE
     }
 
     pub fn set(&mut self, x: usize, y: usize, v: bool) {
+        let bit = W::ONE << (x % W::BITS) as u32;
         if v {
-            self.backing[y][x / WIDTH_U] |= 1 << (x % WIDTH_U)
+            self.row_mut(y)[x / W::BITS] |= bit
         } else {
-            self.backing[y][x / WIDTH_U] &= !(1 << (x % WIDTH_U))
+            self.row_mut(y)[x / W::BITS] &= !bit
         }
     }
 
-    pub fn from_view(view: &dyn BitView) -> BitGrid {
-        let mut backing = vec![];
+    pub fn from_view(view: &dyn BitView<Word = W>) -> BitGrid<W> {
+        let mut out = BitGrid::empty(view.width(), view.height());
 
-        for y in 0..view.height() {
-            let mut line = vec![];
-            for x in 0..(view.width() + (WIDTH_U - 1)) / WIDTH_U {
-                line.push(view.get_backing(x as isize * WIDTH, y as isize));
+        for y in 0..out.height {
+            for (x, w) in out.row_mut(y).iter_mut().enumerate() {
+                *w = view.get_backing((x * W::BITS) as isize, y as isize);
             }
-            backing.push(line);
         }
 
-        BitGrid {
-            backing,
-            width: view.width(),
-            height: view.height(),
+        out
+    }
+
+    // Same as materialising a Shifted view, but straight off the backing words
+    pub fn shifted(&self, x: isize, y: isize) -> BitGrid<W> {
+        let mut out = BitGrid::empty(self.width, self.height);
+        let width = W::BITS as isize;
+        let (words, bits) = (x.div_euclid(width), x.rem_euclid(width) as u32);
+        let unused = self.words() * W::BITS - self.width;
+
+        for oy in 0..self.height {
+            let sy = oy as isize - y;
+            if sy < 0 || sy >= self.height as isize {
+                continue;
+            }
+
+            let source = self.row(sy as usize);
+            let line = out.row_mut(oy);
+            let word = |i: isize| match usize::try_from(i) {
+                Ok(i) => source.get(i).copied().unwrap_or(W::ZERO),
+                _ => W::ZERO,
+            };
+
+            for (i, w) in line.iter_mut().enumerate() {
+                let i = i as isize - words;
+                *w = match bits {
+                    0 => word(i),
+                    _ => word(i) << bits | word(i - 1) >> (W::BITS as u32 - bits),
+                };
+            }
+            if let Some(last) = line.last_mut() {
+                *last &= !W::ZERO >> unused as u32;
+            }
         }
+
+        out
+    }
+
+    // Combines another grid of the same size into this one, a word at a time
+    pub fn apply(&mut self, other: &BitGrid<W>, prim: PrimFn<W>) {
+        for (a, b) in self.backing.iter_mut().zip(&other.backing) {
+            *a = prim(*a, *b);
+        }
+    }
+
+    // Number of set cells
+    pub fn count(&self) -> usize {
+        self.backing.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    // Smallest (min_x, min_y, max_x, max_y) containing every set cell, if any are set
+    pub fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+        let occupied = |l: &[W]| l.iter().any(|w| *w != W::ZERO);
+        let min_y = self.rows().position(occupied)?;
+        let max_y = self.rows().rposition(occupied)?;
+
+        let mut columns = vec![W::ZERO; self.words()];
+        for l in self.rows().skip(min_y).take(max_y + 1 - min_y) {
+            for (c, w) in columns.iter_mut().zip(l) {
+                *c |= *w;
+            }
+        }
+
+        let first = columns.iter().position(|w| *w != W::ZERO)?;
+        let last = columns.iter().rposition(|w| *w != W::ZERO)?;
+        let min_x = first * W::BITS + columns[first].trailing_zeros() as usize;
+        let max_x = last * W::BITS + (W::BITS - 1 - columns[last].leading_zeros() as usize);
+
+        Some((min_x, min_y, max_x, max_y))
     }
 
     #[allow(unused)]
@@ -324,15 +427,314 @@ impl BitGrid {
     }
 }
 
+// Set bits white on black
+impl<W: Word> Visualize for BitGrid<W> {
+    fn frame(&self) -> Frame {
+        let mut frame = Frame::new(self.width, self.height, BLACK);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get(x, y) {
+                    frame.set(x, y, WHITE);
+                }
+            }
+        }
+        frame
+    }
+}
+
+// Geometric transforms. These all copy, and work a word at a time where they can.
+impl<W: Word> BitGrid<W> {
+    // Swaps rows and columns. Works a square tile of one word by one word at a time, transposing
+    // each in place with the usual recursive block swap, so bits are never visited individually.
+    pub fn transpose(&self) -> BitGrid<W> {
+        let mut out = BitGrid::empty(self.height, self.width);
+        let mut tile = vec![W::ZERO; W::BITS];
+
+        for ty in 0..self.height.div_ceil(W::BITS) {
+            for tx in 0..self.words() {
+                for (i, w) in tile.iter_mut().enumerate() {
+                    let y = ty * W::BITS + i;
+                    *w = if y < self.height {
+                        self.row(y)[tx]
+                    } else {
+                        W::ZERO
+                    };
+                }
+
+                transpose_tile(&mut tile);
+
+                for (i, w) in tile.iter().enumerate() {
+                    let y = tx * W::BITS + i;
+                    if y < out.height {
+                        out.row_mut(y)[ty] = *w;
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    pub fn flip_vertical(&self) -> BitGrid<W> {
+        BitGrid {
+            backing: self.rows().rev().flatten().copied().collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub fn flip_horizontal(&self) -> BitGrid<W> {
+        // Reversing the words and the bits within them mirrors each row, but leaves the unused
+        // high bits of the last word at the front. A window then slides them back off.
+        let words = self.words();
+        let reversed = BitGrid {
+            backing: self
+                .rows()
+                .flat_map(|l| l.iter().rev().map(|w| w.reverse_bits()))
+                .collect(),
+            width: words * W::BITS,
+            height: self.height,
+        };
+
+        let unused = (words * W::BITS - self.width) as isize;
+        let w = Window::new(
+            &reversed,
+            unused,
+            0,
+            self.width as isize,
+            self.height as isize,
+        );
+        BitGrid::from_view(&w)
+    }
+
+    // Rotations are clockwise, with y pointing down as it does in print()
+    pub fn rotate90(&self) -> BitGrid<W> {
+        self.transpose().flip_horizontal()
+    }
+
+    pub fn rotate180(&self) -> BitGrid<W> {
+        self.flip_horizontal().flip_vertical()
+    }
+
+    pub fn rotate270(&self) -> BitGrid<W> {
+        self.transpose().flip_vertical()
+    }
+
+    #[allow(unused)]
+    pub fn rotate(&self, quarter_turns: isize) -> BitGrid<W> {
+        match quarter_turns.rem_euclid(4) {
+            0 => self.clone(),
+            1 => self.rotate90(),
+            2 => self.rotate180(),
+            _ => self.rotate270(),
+        }
+    }
+
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> BitGrid<W> {
+        let w = Window::new(
+            self,
+            x as isize,
+            y as isize,
+            width as isize,
+            height as isize,
+        );
+        BitGrid::from_view(&w)
+    }
+
+    // Grows or shrinks the grid, keeping the top left corner where it is
+    #[allow(unused)]
+    pub fn resize(&self, width: usize, height: usize) -> BitGrid<W> {
+        self.crop(0, 0, width, height)
+    }
+
+    // Adds empty space around the grid
+    pub fn pad(&self, left: usize, top: usize, right: usize, bottom: usize) -> BitGrid<W> {
+        let w = Window::new(
+            self,
+            -(left as isize),
+            -(top as isize),
+            (left + self.width + right) as isize,
+            (top + self.height + bottom) as isize,
+        );
+        BitGrid::from_view(&w)
+    }
+}
+
+// Neighbourhood operations, for writing cellular automata as whole grid operations. Cells
+// outside the grid count as empty.
+impl<W: Word> BitGrid<W> {
+    // Sets every cell that is, or is orthogonally adjacent to, a set cell
+    pub fn dilate4(&self) -> BitGrid<W> {
+        let mut out = BitGrid::empty(self.width, self.height);
+        self.dilate4_into(&mut out);
+        out
+    }
+
+    // Same as dilate4, but overwriting a grid of the same size rather than allocating one, for
+    // loops that dilate over and over
+    pub fn dilate4_into(&self, out: &mut BitGrid<W>) {
+        let words = self.words();
+        let top = W::BITS as u32 - 1;
+        let unused = (words * W::BITS - self.width) as u32;
+
+        for y in 0..self.height {
+            let row = self.row(y);
+            let above = (y > 0).then(|| self.row(y - 1));
+            let below = (y + 1 < self.height).then(|| self.row(y + 1));
+            let line = out.row_mut(y);
+
+            for i in 0..words {
+                let carry_up = if i > 0 { row[i - 1] >> top } else { W::ZERO };
+                let carry_down = if i + 1 < words {
+                    row[i + 1] << top
+                } else {
+                    W::ZERO
+                };
+                line[i] = row[i]
+                    | (row[i] << 1 | carry_up)
+                    | (row[i] >> 1 | carry_down)
+                    | above.map_or(W::ZERO, |r| r[i])
+                    | below.map_or(W::ZERO, |r| r[i]);
+            }
+            if let Some(last) = line.last_mut() {
+                *last &= !W::ZERO >> unused;
+            }
+        }
+    }
+
+    // Same as dilate4, but including diagonals. Done as a horizontal then a vertical pass.
+    #[allow(unused)]
+    pub fn dilate8(&self) -> BitGrid<W> {
+        let mut row = self.clone();
+        row.apply(&self.shifted(1, 0), |a, b| a | b);
+        row.apply(&self.shifted(-1, 0), |a, b| a | b);
+
+        let mut out = row.clone();
+        out.apply(&row.shifted(0, -1), |a, b| a | b);
+        out.apply(&row.shifted(0, 1), |a, b| a | b);
+        out
+    }
+
+    // Keeps only the cells that are set along with all four orthogonal neighbours
+    #[allow(unused)]
+    pub fn erode(&self) -> BitGrid<W> {
+        let mut out = self.clone();
+        for (dx, dy) in [(1, 0), (-1, 0), (0, -1), (0, 1)] {
+            out.apply(&self.shifted(dx, dy), |a, b| a & b);
+        }
+        out
+    }
+
+    // Counts the set cells among the eight surrounding each cell. Each shifted copy of the grid
+    // is pushed through a ripple carry adder, so the count for every cell is computed at once.
+    #[allow(unused)]
+    pub fn neighbour_count(&self) -> NeighbourCount<W> {
+        let mut planes = [
+            BitGrid::empty(self.width, self.height),
+            BitGrid::empty(self.width, self.height),
+            BitGrid::empty(self.width, self.height),
+            BitGrid::empty(self.width, self.height),
+        ];
+
+        for (dx, dy) in [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ] {
+            let mut carry = self.shifted(dx, dy);
+
+            for plane in planes.iter_mut() {
+                for (p, c) in plane.backing.iter_mut().zip(carry.backing.iter_mut()) {
+                    let sum = *p ^ *c;
+                    *c &= *p;
+                    *p = sum;
+                }
+            }
+        }
+
+        NeighbourCount { planes }
+    }
+}
+
+// Bit sliced neighbour counts. Bit n of a cell's count is stored in planes[n].
+pub struct NeighbourCount<W: Word = u128> {
+    pub planes: [BitGrid<W>; 4],
+}
+
+impl<W: Word> NeighbourCount<W> {
+    #[allow(unused)]
+    pub fn get(&self, x: usize, y: usize) -> usize {
+        self.planes
+            .iter()
+            .enumerate()
+            .map(|(n, p)| (p.get(x, y) as usize) << n)
+            .sum()
+    }
+
+    // A grid with every cell set that has exactly `count` neighbours
+    #[allow(unused)]
+    pub fn equals(&self, count: usize) -> BitGrid<W> {
+        let mut out = self.planes[0].clone();
+
+        for (i, w) in out.backing.iter_mut().enumerate() {
+            *w = !W::ZERO;
+            for (n, p) in self.planes.iter().enumerate() {
+                *w &= if count >> n & 1 == 1 {
+                    p.backing[i]
+                } else {
+                    !p.backing[i]
+                };
+            }
+        }
+
+        // Zero counts match the unused bits past the right edge too, so strip them back off
+        let unused = out.words() * W::BITS - out.width;
+        for y in 0..out.height {
+            if let Some(last) = out.row_mut(y).last_mut() {
+                *last &= !W::ZERO >> unused as u32;
+            }
+        }
+
+        out
+    }
+}
+
+// In place transpose of a square tile, where bit x of tile[y] is the cell at (x, y). Each round
+// swaps the off diagonal quadrants of every block, halving the block size until it reaches 1.
+fn transpose_tile<W: Word>(tile: &mut [W]) {
+    let mut j = W::BITS / 2;
+    let mut mask = !W::ZERO >> j as u32;
+
+    while j != 0 {
+        for k in 0..W::BITS {
+            if k & j == 0 {
+                let t = ((tile[k] >> j as u32) ^ tile[k + j]) & mask;
+                tile[k] ^= t << j as u32;
+                tile[k + j] ^= t;
+            }
+        }
+        j >>= 1;
+        mask ^= mask << j as u32;
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use std::time::Duration;
 
+    const WIDTH_U: usize = u128::BITS as usize;
+    const WIDTH: isize = WIDTH_U as isize;
+
     #[test]
     fn window_clamp() {
         let bit_test = |x_set, backing_x, window_x, window_size| {
-            let mut b = BitGrid::new(128, 1);
+            let mut b = BitGrid::new(WIDTH_U * 2, 1);
             b.set(x_set, 0, true);
             let w = Window::new(&b, window_x, 0, window_size, 1);
             let v = BitGrid::from_view(&w);
@@ -343,15 +745,177 @@ mod test {
         assert_eq!(bit_test(3, 0, 0, 2), 0);
         assert_eq!(bit_test(3, 0, 0, WIDTH), 1 << 3);
         assert_eq!(bit_test(WIDTH_U + 3, WIDTH, 0, WIDTH + 1), 0);
-        assert_eq!(bit_test(WIDTH_U + 3, 0, 4, WIDTH), 1 << 63);
+        assert_eq!(bit_test(WIDTH_U + 3, 0, 4, WIDTH), 1 << (WIDTH - 1));
+    }
+
+    fn scattered<W: Word>(width: usize, height: usize) -> BitGrid<W> {
+        let mut b = BitGrid::empty(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                b.set(x, y, (x * 7 + y * 13 + x * y) % 5 == 0);
+            }
+        }
+        b
+    }
+
+    fn same<W: Word>(a: &BitGrid<W>, b: &BitGrid<W>) -> bool {
+        a.width() == b.width()
+            && a.height() == b.height()
+            && (0..a.height()).all(|y| (0..a.width()).all(|x| a.get(x, y) == b.get(x, y)))
+    }
+
+    // The grid tests run at every word size, as the word boundaries are where things go wrong
+    macro_rules! each_word {
+        ($check:ident) => {
+            $check::<u64>();
+            $check::<u128>();
+            #[cfg(feature = "wide")]
+            $check::<super::super::word::Wide>();
+        };
+    }
+
+    #[test]
+    fn transforms() {
+        each_word!(check_transforms);
+    }
+
+    #[test]
+    fn crop_and_pad() {
+        each_word!(check_crop_and_pad);
+    }
+
+    #[test]
+    fn neighbourhoods() {
+        each_word!(check_neighbourhoods);
+    }
+
+    #[test]
+    fn views() {
+        each_word!(check_views);
+    }
+
+    fn matches<W: Word>(v: &BitGrid<W>, expected: impl Fn(isize, isize) -> bool) -> bool {
+        (0..v.height()).all(|y| (0..v.width()).all(|x| v.get(x, y) == expected(x as _, y as _)))
+    }
+
+    // Every view against working it out a bit at a time, at offsets either side of the word
+    // boundaries, including ones more than a word to the left
+    fn check_views<W: Word>() {
+        let b = scattered::<W>(150, 5);
+        let (w, h) = (b.width() as isize, b.height() as isize);
+        let at = |x: isize, y: isize| x >= 0 && x < w && y >= 0 && y < h && b.get(x as _, y as _);
+
+        for dx in [-300, -129, -64, -1, 0, 1, 63, 64, 65, 130] {
+            for dy in [-2, 0, 1] {
+                let shifted = BitGrid::from_view(&Shifted::new(&b, dx, dy));
+                assert!(matches(&shifted, |x, y| at(x - dx, y - dy)));
+                assert!(same(&shifted, &b.shifted(dx, dy)));
+
+                let wrapped = BitGrid::from_view(&ShiftedWrap::new(&b, dx, dy));
+                let wrap = |x: isize, y: isize| at((x - dx).rem_euclid(w), (y - dy).rem_euclid(h));
+                assert!(matches(&wrapped, wrap));
+
+                let window = BitGrid::from_view(&Window::new(&b, dx, dy, 100, 4));
+                assert!(matches(&window, |x, y| at(x + dx, y + dy)));
+            }
+        }
+    }
+
+    fn check_transforms<W: Word>() {
+        let b = scattered::<W>(200, 150);
+
+        let t = b.transpose();
+        let r = b.rotate90();
+        let h = b.flip_horizontal();
+        let v = b.flip_vertical();
+
+        assert_eq!((t.width(), t.height()), (150, 200));
+        assert_eq!((r.width(), r.height()), (150, 200));
+
+        for y in 0..b.height() {
+            for x in 0..b.width() {
+                assert_eq!(t.get(y, x), b.get(x, y));
+                assert_eq!(r.get(b.height() - 1 - y, x), b.get(x, y));
+                assert_eq!(h.get(b.width() - 1 - x, y), b.get(x, y));
+                assert_eq!(v.get(x, b.height() - 1 - y), b.get(x, y));
+            }
+        }
+
+        assert!(same(&b.rotate(4), &b));
+        assert!(same(&b.rotate(-1), &b.rotate270()));
+        assert!(same(&b.rotate90().rotate90(), &b.rotate180()));
+        assert!(same(&b.rotate90().rotate270(), &b));
+    }
+
+    fn check_crop_and_pad<W: Word>() {
+        let b = scattered::<W>(200, 150);
+
+        let c = b.crop(130, 10, 40, 20);
+        for y in 0..20 {
+            for x in 0..40 {
+                assert_eq!(c.get(x, y), b.get(x + 130, y + 10));
+            }
+        }
+
+        let p = b.pad(140, 3, 5, 7);
+        assert_eq!((p.width(), p.height()), (345, 160));
+        assert!(same(&p.crop(140, 3, 200, 150), &b));
+        assert_eq!(p.count(), b.count());
+
+        let r = b.resize(100, 200);
+        assert!(same(&r.crop(0, 0, 100, 150), &b.crop(0, 0, 100, 150)));
+        assert!(!(150..200).any(|y| (0..100).any(|x| r.get(x, y))));
+    }
+
+    fn check_neighbourhoods<W: Word>() {
+        let b = scattered::<W>(200, 150);
+        let counts = b.neighbour_count();
+        let d4 = b.dilate4();
+        let d8 = b.dilate8();
+        let e = b.erode();
+        let equal = (0..=9).map(|n| counts.equals(n)).collect::<Vec<_>>();
+
+        let at = |x: isize, y: isize| {
+            x >= 0 && y >= 0 && x < 200 && y < 150 && b.get(x as usize, y as usize)
+        };
+
+        for y in 0..150 {
+            for x in 0..200 {
+                let (xi, yi) = (x as isize, y as isize);
+                let cross = [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)];
+                let ring = (-1..=1)
+                    .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                    .filter(|&(dx, dy)| at(xi + dx, yi + dy));
+
+                assert_eq!(
+                    d4.get(x, y),
+                    cross.iter().any(|(dx, dy)| at(xi + dx, yi + dy))
+                );
+                assert_eq!(
+                    e.get(x, y),
+                    cross.iter().all(|(dx, dy)| at(xi + dx, yi + dy))
+                );
+                assert_eq!(d8.get(x, y), ring.clone().count() > 0);
+
+                let n = ring.count() - at(xi, yi) as usize;
+                assert_eq!(counts.get(x, y), n);
+                assert!(equal[n].get(x, y));
+                assert!(!equal[n + 1].get(x, y));
+            }
+        }
+
+        // count() sees the unused bits past the right edge too, so this checks they're clear
+        let cells = (0..150).map(|y| (0..200).filter(|&x| equal[0].get(x, y)).count());
+        assert_eq!(equal[0].count(), cells.sum());
     }
 
     // Visual Test
+    #[allow(unused)]
     fn repeat() {
         let mut b = BitGrid::new(16, 4);
         b.set(1, 1, true);
 
-        let mut w = Window::new(&b, 1, 1, 14, 2);
+        let w = Window::new(&b, 1, 1, 14, 2);
 
         for n in 0..32 {
             let s = ShiftedWrap::new(&w, n, n);
@@ -367,6 +931,7 @@ mod test {
     }
 
     // Visual Test
+    #[allow(unused)]
     fn repeat_2() {
         {
             let mut b = BitGrid::new(16, 1);
@@ -397,12 +962,13 @@ mod test {
     }
 
     // Visual Test
+    #[allow(unused)]
     fn bit_grid_shift() {
         let mut b = BitGrid::new(128, 16);
         b.set(63, 5, true);
         b.print();
 
-        for x in 0..1000 {
+        for _ in 0..1000 {
             std::thread::sleep(Duration::from_millis(100));
 
             let sa = Shifted::new(&b, 1, 0);
@@ -0,0 +1,162 @@
+// A 3D bit grid, stored as a stack of BitGrid layers along z. Unlike BitGrid there are no views,
+// every operation copies. Shifts and masks work a whole layer at a time, so voxel puzzles can be
+// written as a handful of grid operations instead of walking individual cubes.
+
+use super::bit_grid::BitGrid;
+use super::word::Word;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct BitGrid3<W: Word = u128> {
+    layers: Vec<BitGrid<W>>,
+    width: usize,
+    height: usize,
+}
+
+// The six face neighbours
+pub const FACES: [(isize, isize, isize); 6] = [
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, -1, 0),
+    (0, 1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+impl BitGrid3 {
+    pub fn new(width: usize, height: usize, depth: usize) -> BitGrid3 {
+        BitGrid3::empty(width, height, depth)
+    }
+}
+
+impl<W: Word> BitGrid3<W> {
+    pub fn empty(width: usize, height: usize, depth: usize) -> BitGrid3<W> {
+        BitGrid3 {
+            layers: vec![BitGrid::empty(width, height); depth],
+            width,
+            height,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.layers.len()
+    }
+
+    #[allow(unused)]
+    pub fn get(&self, x: usize, y: usize, z: usize) -> bool {
+        self.layers[z].get(x, y)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, v: bool) {
+        self.layers[z].set(x, y, v)
+    }
+
+    // Moves every cell by (x, y, z). Cells shifted in from outside the grid are empty.
+    pub fn shifted(&self, x: isize, y: isize, z: isize) -> BitGrid3<W> {
+        let layers = (0..self.depth() as isize)
+            .map(|oz| match usize::try_from(oz - z) {
+                Ok(sz) if sz < self.depth() => self.layers[sz].shifted(x, y),
+                _ => BitGrid::empty(self.width, self.height),
+            })
+            .collect();
+
+        BitGrid3 {
+            layers,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    // Combines another grid of the same size into this one, a word at a time
+    pub fn apply(&mut self, other: &BitGrid3<W>, prim: fn(W, W) -> W) {
+        for (a, b) in self.layers.iter_mut().zip(&other.layers) {
+            a.apply(b, prim);
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.layers.iter().map(|l| l.count()).sum()
+    }
+
+    // Sets every cell that is, or shares a face with, a set cell
+    #[allow(unused)]
+    pub fn dilate6(&self) -> BitGrid3<W> {
+        let mut out = BitGrid3::empty(self.width, self.height, self.depth());
+        self.dilate6_into(&mut out);
+        out
+    }
+
+    // Same as dilate6, but overwriting a grid of the same size rather than allocating one
+    pub fn dilate6_into(&self, out: &mut BitGrid3<W>) {
+        for (z, layer) in out.layers.iter_mut().enumerate() {
+            self.layers[z].dilate4_into(layer);
+            if z > 0 {
+                layer.apply(&self.layers[z - 1], |a, b| a | b);
+            }
+            if z + 1 < self.layers.len() {
+                layer.apply(&self.layers[z + 1], |a, b| a | b);
+            }
+        }
+    }
+
+    // Grows the set cells through face neighbours, never entering a cell set in `blocked`,
+    // until nothing changes. Each round is a dilation followed by a mask, flipping between two
+    // grids so nothing is allocated once it's going.
+    pub fn flood_fill(&self, blocked: &BitGrid3<W>) -> BitGrid3<W> {
+        let mut filled = self.clone();
+        filled.apply(blocked, |a, b| a & !b);
+        let mut next = filled.clone();
+
+        loop {
+            filled.dilate6_into(&mut next);
+            next.apply(blocked, |a, b| a & !b);
+
+            // Stops comparing at the first word that differs, which while it's still growing
+            // is usually early on
+            if next == filled {
+                return next;
+            }
+            std::mem::swap(&mut filled, &mut next);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shift_and_fill() {
+        // A 3x3x3 cube with its centre hollowed out, in the middle of a 7x7x7 grid
+        let mut shell = BitGrid3::new(7, 7, 7);
+        for z in 2..5 {
+            for y in 2..5 {
+                for x in 2..5 {
+                    shell.set(x, y, z, (x, y, z) != (3, 3, 3));
+                }
+            }
+        }
+
+        // Pushes the last layer of the shell out through the far side of the grid
+        let s = shell.shifted(1, -1, 3);
+        assert!(s.get(3, 1, 5));
+        assert!(!s.get(4, 2, 6));
+        assert_eq!(s.count(), 9 + 8);
+
+        let mut seed = BitGrid3::new(7, 7, 7);
+        seed.set(0, 0, 0, true);
+        let outside = seed.flood_fill(&shell);
+        assert_eq!(outside.count(), 7 * 7 * 7 - 27);
+        assert!(!outside.get(3, 3, 3));
+
+        let mut seed = BitGrid3::new(7, 7, 7);
+        seed.set(3, 3, 3, true);
+        assert_eq!(seed.flood_fill(&shell).count(), 1);
+
+        // Across a word boundary
+        let mut seed = BitGrid3::<u64>::empty(130, 3, 2);
+        seed.set(64, 1, 0, true);
+        let grown = seed.dilate6();
+        assert_eq!(grown.count(), 6);
+        assert!(grown.get(65, 1, 0) && grown.get(63, 1, 0) && grown.get(64, 1, 1));
+    }
+}
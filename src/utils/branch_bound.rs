@@ -0,0 +1,206 @@
+// Depth first branch and bound. A problem describes how to expand a node, what a node is worth
+// as a solution, and an optimistic bound on what anything below it could be worth. The solver
+// keeps the best node seen so far (the incumbent), explores the most promising children first,
+// and skips any child whose bound can't beat the incumbent.
+//
+// The bound must never underestimate, or the search can prune its way past the real answer.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+pub trait BranchAndBound {
+    type Node: Copy + Eq + Hash;
+    type Value: Copy + Ord;
+    type Key: Ord;
+
+    fn successors(&self, node: &Self::Node) -> Vec<Self::Node>;
+
+    // What the node is worth if the search stopped here
+    fn value(&self, node: &Self::Node) -> Self::Value;
+
+    // The most that the node, or anything reachable from it, could be worth
+    fn upper_bound(&self, node: &Self::Node) -> Self::Value;
+
+    // Children are explored from highest key to lowest
+    fn key(&self, node: &Self::Node) -> Self::Key;
+
+    // Only needed for iterative deepening, where nodes this deep or deeper aren't expanded until
+    // the limit is raised past them
+    fn depth(&self, _node: &Self::Node) -> usize {
+        0
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Stats {
+    // Nodes whose children were generated
+    pub expanded: usize,
+    // Children skipped because their bound couldn't beat the incumbent
+    pub pruned: usize,
+    // Children skipped because the same node had already been searched
+    pub repeated: usize,
+    pub iterations: usize,
+}
+
+pub struct Solver<'a, P: BranchAndBound> {
+    problem: &'a P,
+    memoize: bool,
+    deepening: Option<usize>,
+
+    best: Option<P::Node>,
+    seen: HashSet<P::Node>,
+    cut_off: bool,
+    stats: Stats,
+}
+
+impl<'a, P: BranchAndBound> Solver<'a, P> {
+    pub fn new(problem: &'a P) -> Solver<'a, P> {
+        Solver {
+            problem,
+            memoize: false,
+            deepening: None,
+            best: None,
+            seen: HashSet::new(),
+            cut_off: false,
+            stats: Stats::default(),
+        }
+    }
+
+    // Remembers every node searched, so that reaching one again by another route is free. This
+    // costs memory and a hash per node, so it only pays when routes converge often.
+    #[allow(unused)]
+    pub fn memoize(mut self) -> Solver<'a, P> {
+        self.memoize = true;
+        self
+    }
+
+    // Searches to a depth limit, then raises the limit by `step` and searches again, until
+    // nothing is cut off. The incumbent carries over, so each pass prunes harder than the last.
+    #[allow(unused)]
+    pub fn deepening(mut self, step: usize) -> Solver<'a, P> {
+        assert!(step > 0);
+        self.deepening = Some(step);
+        self
+    }
+
+    #[allow(unused)]
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub fn solve(&mut self, root: P::Node) -> P::Node {
+        self.best = Some(root);
+
+        let mut limit = self.deepening.unwrap_or(usize::MAX);
+        loop {
+            self.stats.iterations += 1;
+            self.cut_off = false;
+            self.seen.clear();
+
+            self.search(root, limit);
+
+            if !self.cut_off {
+                return self.best.unwrap();
+            }
+            limit = limit.saturating_add(self.deepening.unwrap());
+        }
+    }
+
+    fn search(&mut self, node: P::Node, limit: usize) {
+        if self.problem.depth(&node) >= limit {
+            self.cut_off = true;
+            return;
+        }
+
+        let p = self.problem;
+        let mut children = p.successors(&node);
+        self.stats.expanded += 1;
+
+        children.sort_by_cached_key(|c| std::cmp::Reverse(p.key(c)));
+
+        for child in children {
+            let best = self.best.map(|b| p.value(&b));
+
+            if best.is_some_and(|b| b >= p.upper_bound(&child)) {
+                self.stats.pruned += 1;
+                continue;
+            }
+
+            if best.is_none_or(|b| p.value(&child) > b) {
+                self.best = Some(child);
+            }
+
+            if self.memoize && !self.seen.insert(child) {
+                self.stats.repeated += 1;
+                continue;
+            }
+
+            self.search(child, limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 0/1 knapsack, choosing items in order. A node is (next item, weight, value).
+    struct Knapsack {
+        items: Vec<(usize, usize)>,
+        capacity: usize,
+    }
+
+    impl BranchAndBound for Knapsack {
+        type Node = (usize, usize, usize);
+        type Value = usize;
+        type Key = usize;
+
+        fn successors(&self, &(i, w, v): &Self::Node) -> Vec<Self::Node> {
+            let Some(&(iw, iv)) = self.items.get(i) else {
+                return vec![];
+            };
+
+            let mut out = vec![(i + 1, w, v)];
+            if w + iw <= self.capacity {
+                out.push((i + 1, w + iw, v + iv));
+            }
+            out
+        }
+
+        fn value(&self, n: &Self::Node) -> usize {
+            n.2
+        }
+
+        // As if every remaining item fit
+        fn upper_bound(&self, &(i, _, v): &Self::Node) -> usize {
+            v + self.items[i..].iter().map(|(_, iv)| iv).sum::<usize>()
+        }
+
+        fn key(&self, n: &Self::Node) -> usize {
+            self.upper_bound(n)
+        }
+
+        fn depth(&self, n: &Self::Node) -> usize {
+            n.0
+        }
+    }
+
+    #[test]
+    fn knapsack() {
+        let k = Knapsack {
+            items: vec![(5, 10), (4, 40), (6, 30), (3, 50), (2, 5), (7, 35)],
+            capacity: 12,
+        };
+
+        let mut plain = Solver::new(&k);
+        assert_eq!(plain.solve((0, 0, 0)).2, 100);
+        assert!(plain.stats().pruned > 0);
+
+        let mut memo = Solver::new(&k).memoize();
+        assert_eq!(memo.solve((0, 0, 0)).2, 100);
+
+        let mut deep = Solver::new(&k).deepening(2);
+        assert_eq!(deep.solve((0, 0, 0)).2, 100);
+        assert!(deep.stats().iterations > 1);
+    }
+}
@@ -0,0 +1,149 @@
+// Fast forwarding deterministic simulations. If a simulation ever gets back to a state it has
+// been in before, it will loop from there forever, and anything that changes by the same amount
+// each time around the loop can be extrapolated to any step count without simulating that far.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// The state after `start + length` steps is the same as the state after `start` steps. Holds
+// the metric after each step up to that point, with the initial state's at index 0.
+#[derive(Debug)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+    metrics: Vec<isize>,
+}
+
+impl Cycle {
+    // The metric after `n` steps, assuming it grows by the same amount every time round
+    pub fn extrapolate(&self, n: usize) -> isize {
+        if n < self.metrics.len() {
+            return self.metrics[n];
+        }
+
+        let offset = n - self.start;
+        let (cycles, rem) = (offset / self.length, offset % self.length);
+        let per_cycle = self.metrics[self.start + self.length] - self.metrics[self.start];
+
+        self.metrics[self.start + rem] + cycles as isize * per_cycle
+    }
+
+    // The earliest step whose state is the same as the state after `n` steps
+    pub fn equivalent(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.length
+        }
+    }
+}
+
+// Remembers the key of every state seen, so it stops at the first repeat. Keys must capture
+// everything that affects future steps. The state is left wherever the search stopped.
+pub fn find_hashed<S, K: Hash + Eq>(
+    state: &mut S,
+    mut step: impl FnMut(&mut S),
+    mut key: impl FnMut(&S) -> K,
+    mut metric: impl FnMut(&S) -> isize,
+) -> Cycle {
+    let mut seen = HashMap::new();
+    let mut metrics = vec![];
+
+    loop {
+        let n = metrics.len();
+        metrics.push(metric(state));
+
+        if let Some(start) = seen.insert(key(state), n) {
+            return Cycle {
+                start,
+                length: n - start,
+                metrics,
+            };
+        }
+
+        step(state);
+    }
+}
+
+// Brent's algorithm only ever holds two states, rather than a key for each one, at the cost of
+// stepping about three times as often. Better when states are big and cycles are long.
+#[allow(unused)]
+pub fn find_brent<S: Clone, K: Eq>(
+    initial: &S,
+    mut step: impl FnMut(&mut S),
+    mut key: impl FnMut(&S) -> K,
+    mut metric: impl FnMut(&S) -> isize,
+) -> Cycle {
+    // Finds the length, by moving the tortoise up to the hare at each power of two, until the
+    // hare runs into it
+    let (mut power, mut length) = (1, 1);
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    step(&mut hare);
+
+    while key(&tortoise) != key(&hare) {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        step(&mut hare);
+        length += 1;
+    }
+
+    // Then the start, by starting the hare a cycle ahead and walking both until they meet
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    let mut metrics = vec![metric(&hare)];
+    for _ in 0..length {
+        step(&mut hare);
+        metrics.push(metric(&hare));
+    }
+
+    let mut start = 0;
+    while key(&tortoise) != key(&hare) {
+        step(&mut tortoise);
+        step(&mut hare);
+        metrics.push(metric(&hare));
+        start += 1;
+    }
+
+    Cycle {
+        start,
+        length,
+        metrics,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Counts up forever, while the state runs 0..12 then loops round 5..12
+    fn step(s: &mut (usize, isize)) {
+        s.0 = if s.0 == 11 { 5 } else { s.0 + 1 };
+        s.1 += s.0 as isize;
+    }
+
+    #[test]
+    fn cycles() {
+        let mut state = (0, 0);
+        let hashed = find_hashed(&mut state, step, |s| s.0, |s| s.1);
+        let brent = find_brent(&(0, 0), step, |s| s.0, |s| s.1);
+
+        for c in [&hashed, &brent] {
+            assert_eq!((c.start, c.length), (5, 7));
+        }
+
+        let mut state = (0, 0);
+        for n in 0..100 {
+            assert_eq!(hashed.extrapolate(n), state.1);
+            assert_eq!(brent.extrapolate(n), state.1);
+            assert_eq!(
+                hashed.equivalent(n),
+                if n < 12 { n } else { 5 + (n - 5) % 7 }
+            );
+            step(&mut state);
+        }
+    }
+}
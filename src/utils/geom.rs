@@ -0,0 +1,289 @@
+// Points and compass directions. Like the puzzle inputs, y points down, so North is -y and
+// turning right is clockwise on screen.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point2 {
+    pub x: isize,
+    pub y: isize,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+impl Point2 {
+    pub const ORIGIN: Point2 = Point2::new(0, 0);
+
+    pub const fn new(x: isize, y: isize) -> Point2 {
+        Point2 { x, y }
+    }
+
+    pub fn manhattan(self, other: Point2) -> isize {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    // The number of king moves between two points
+    pub fn chebyshev(self, other: Point2) -> isize {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+
+    // Each coordinate clamped to -1, 0 or 1, i.e. one step towards the point from the origin
+    pub fn signum(self) -> Point2 {
+        Point2::new(self.x.signum(), self.y.signum())
+    }
+}
+
+impl Point3 {
+    #[allow(unused)]
+    pub const ORIGIN: Point3 = Point3::new(0, 0, 0);
+
+    pub const fn new(x: isize, y: isize, z: isize) -> Point3 {
+        Point3 { x, y, z }
+    }
+
+    #[allow(unused)]
+    pub fn manhattan(self, other: Point3) -> isize {
+        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+    }
+}
+
+impl From<(isize, isize)> for Point2 {
+    fn from((x, y): (isize, isize)) -> Point2 {
+        Point2::new(x, y)
+    }
+}
+
+impl From<(isize, isize, isize)> for Point3 {
+    fn from((x, y, z): (isize, isize, isize)) -> Point3 {
+        Point3::new(x, y, z)
+    }
+}
+
+macro_rules! point_ops {
+    ($t:ident, $($f:ident),*) => {
+        impl Add for $t {
+            type Output = $t;
+
+            fn add(self, other: $t) -> $t {
+                $t { $($f: self.$f + other.$f),* }
+            }
+        }
+
+        impl Sub for $t {
+            type Output = $t;
+
+            fn sub(self, other: $t) -> $t {
+                $t { $($f: self.$f - other.$f),* }
+            }
+        }
+
+        impl Mul<isize> for $t {
+            type Output = $t;
+
+            fn mul(self, n: isize) -> $t {
+                $t { $($f: self.$f * n),* }
+            }
+        }
+
+        impl Neg for $t {
+            type Output = $t;
+
+            fn neg(self) -> $t {
+                $t { $($f: -self.$f),* }
+            }
+        }
+
+        impl AddAssign for $t {
+            fn add_assign(&mut self, other: $t) {
+                *self = *self + other;
+            }
+        }
+
+        impl SubAssign for $t {
+            fn sub_assign(&mut self, other: $t) {
+                *self = *self - other;
+            }
+        }
+    };
+}
+
+point_ops!(Point2, x, y);
+point_ops!(Point3, x, y, z);
+
+// In clockwise order starting from East, which is also the order day 22 scores facings in
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Dir4 {
+    East,
+    South,
+    West,
+    North,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Dir8 {
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+    North,
+    NorthEast,
+}
+
+impl Dir4 {
+    pub const ALL: [Dir4; 4] = [Dir4::East, Dir4::South, Dir4::West, Dir4::North];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(i: usize) -> Dir4 {
+        Dir4::ALL[i % 4]
+    }
+
+    pub fn rotate_right(self) -> Dir4 {
+        Dir4::from_index(self.index() + 1)
+    }
+
+    pub fn rotate_left(self) -> Dir4 {
+        Dir4::from_index(self.index() + 3)
+    }
+
+    #[allow(unused)]
+    pub fn opposite(self) -> Dir4 {
+        Dir4::from_index(self.index() + 2)
+    }
+
+    pub fn delta(self) -> Point2 {
+        Dir8::from(self).delta()
+    }
+}
+
+impl Dir8 {
+    pub const ALL: [Dir8; 8] = [
+        Dir8::East,
+        Dir8::SouthEast,
+        Dir8::South,
+        Dir8::SouthWest,
+        Dir8::West,
+        Dir8::NorthWest,
+        Dir8::North,
+        Dir8::NorthEast,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(i: usize) -> Dir8 {
+        Dir8::ALL[i % 8]
+    }
+
+    // Turns 45 degrees
+    #[allow(unused)]
+    pub fn rotate_right(self) -> Dir8 {
+        Dir8::from_index(self.index() + 1)
+    }
+
+    #[allow(unused)]
+    pub fn rotate_left(self) -> Dir8 {
+        Dir8::from_index(self.index() + 7)
+    }
+
+    #[allow(unused)]
+    pub fn opposite(self) -> Dir8 {
+        Dir8::from_index(self.index() + 4)
+    }
+
+    pub fn delta(self) -> Point2 {
+        let (x, y) = match self {
+            Dir8::East => (1, 0),
+            Dir8::SouthEast => (1, 1),
+            Dir8::South => (0, 1),
+            Dir8::SouthWest => (-1, 1),
+            Dir8::West => (-1, 0),
+            Dir8::NorthWest => (-1, -1),
+            Dir8::North => (0, -1),
+            Dir8::NorthEast => (1, -1),
+        };
+        Point2::new(x, y)
+    }
+}
+
+impl From<Dir4> for Dir8 {
+    fn from(d: Dir4) -> Dir8 {
+        Dir8::from_index(d.index() * 2)
+    }
+}
+
+impl Add<Dir4> for Point2 {
+    type Output = Point2;
+
+    fn add(self, d: Dir4) -> Point2 {
+        self + d.delta()
+    }
+}
+
+impl Add<Dir8> for Point2 {
+    type Output = Point2;
+
+    fn add(self, d: Dir8) -> Point2 {
+        self + d.delta()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn points() {
+        let a = Point2::new(3, -4);
+        let b = Point2::from((-1, 2));
+
+        assert_eq!(a + b, Point2::new(2, -2));
+        assert_eq!(a - b, Point2::new(4, -6));
+        assert_eq!(-a * 2, Point2::new(-6, 8));
+        assert_eq!(a.manhattan(b), 10);
+        assert_eq!(a.chebyshev(b), 6);
+        assert_eq!((a - b).signum(), Point2::new(1, -1));
+
+        let mut p = Point3::new(1, 2, 3);
+        p += Point3::new(1, 1, 1);
+        p -= Point3::new(0, 0, 5);
+        assert_eq!(p, Point3::new(2, 3, -1));
+        assert_eq!(p.manhattan(Point3::ORIGIN), 6);
+    }
+
+    #[test]
+    fn directions() {
+        assert_eq!(Dir4::North.rotate_right(), Dir4::East);
+        assert_eq!(Dir4::East.rotate_left(), Dir4::North);
+        assert_eq!(Dir4::West.opposite(), Dir4::East);
+        assert_eq!(Point2::ORIGIN + Dir4::North, Point2::new(0, -1));
+
+        assert_eq!(Dir8::North.rotate_right(), Dir8::NorthEast);
+        assert_eq!(Dir8::East.rotate_left(), Dir8::NorthEast);
+        assert_eq!(Dir8::NorthWest.opposite(), Dir8::SouthEast);
+
+        for d in Dir4::ALL {
+            assert_eq!(d.delta() + d.opposite().delta(), Point2::ORIGIN);
+            assert_eq!(d.rotate_left().rotate_right(), d);
+        }
+
+        for d in Dir8::ALL {
+            assert_eq!(d.delta() + d.opposite().delta(), Point2::ORIGIN);
+            assert_eq!(d.rotate_right().rotate_right().delta(), {
+                // A right angle turn clockwise, with y pointing down, maps (x, y) to (-y, x)
+                let Point2 { x, y } = d.delta();
+                Point2::new(-y, x)
+            });
+        }
+    }
+}
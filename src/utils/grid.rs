@@ -0,0 +1,223 @@
+// A dense 2D grid of anything, stored row by row in a single Vec. Positions are (x, y) with y
+// pointing down, as they are in the puzzle inputs. Indexing with a (usize, usize) panics when out
+// of bounds, while get() takes signed coordinates and returns None, which suits walking off the
+// edge of the map.
+
+use std::fmt::{Display, Formatter};
+use std::ops::{Index, IndexMut};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+const OFFSETS_4: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+const OFFSETS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+];
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T> {
+        Grid {
+            cells: vec![fill; width * height],
+            width,
+            height,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    // Builds a grid from lines of text, mapping each character to a cell. Lines shorter than the
+    // longest are padded as if they ended in spaces.
+    pub fn parse(input: &str, map: impl FnMut(char) -> T) -> Grid<T> {
+        Grid::from_lines(input.lines(), map)
+    }
+
+    pub fn from_lines<'a>(
+        lines: impl IntoIterator<Item = &'a str>,
+        mut map: impl FnMut(char) -> T,
+    ) -> Grid<T> {
+        let lines = lines.into_iter().collect::<Vec<&str>>();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for l in &lines {
+            let mut chars = l.chars();
+            for _ in 0..width {
+                cells.push(map(chars.next().unwrap_or(' ')));
+            }
+        }
+
+        Grid {
+            cells,
+            width,
+            height: lines.len(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && x < self.width as isize && y < self.height as isize
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
+        if self.contains(x, y) {
+            Some(&self.cells[y as usize * self.width + x as usize])
+        } else {
+            None
+        }
+    }
+
+    // Brings a position back onto the grid, as if the edges were joined up
+    pub fn wrap(&self, x: isize, y: isize) -> (usize, usize) {
+        (
+            x.rem_euclid(self.width as isize) as usize,
+            y.rem_euclid(self.height as isize) as usize,
+        )
+    }
+
+    #[allow(unused)]
+    pub fn get_wrapping(&self, x: isize, y: isize) -> &T {
+        &self[self.wrap(x, y)]
+    }
+
+    // The orthogonal neighbours of a position that are on the grid, clockwise from north
+    #[allow(unused)]
+    pub fn neighbours4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours(x, y, &OFFSETS_4)
+    }
+
+    // As neighbours4, including diagonals, clockwise from north west
+    #[allow(unused)]
+    pub fn neighbours8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours(x, y, &OFFSETS_8)
+    }
+
+    fn neighbours(
+        &self,
+        x: usize,
+        y: usize,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        offsets.iter().filter_map(move |(dx, dy)| {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            self.contains(nx, ny).then_some((nx as usize, ny as usize))
+        })
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        &mut self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    #[allow(unused)]
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> {
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    pub fn column_mut(&mut self, x: usize) -> impl DoubleEndedIterator<Item = &mut T> {
+        self.cells[x..].iter_mut().step_by(self.width)
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> {
+        // chunks() panics on zero, but an empty grid has no cells to chunk anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    // Every cell along with its position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, c)| ((i % width, i / width), c))
+    }
+
+    pub fn position(&self, mut pred: impl FnMut(&T) -> bool) -> Option<(usize, usize)> {
+        self.iter().find(|(_, c)| pred(c)).map(|(p, _)| p)
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(x < self.width, "x {x} outside grid of width {}", self.width);
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(x < self.width, "x {x} outside grid of width {}", self.width);
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows() {
+            for c in row {
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grid() {
+        let g = Grid::parse("123\n45\n789", |c| c.to_digit(10));
+
+        assert_eq!((g.width(), g.height()), (3, 3));
+        assert_eq!(g[(1, 2)], Some(8));
+        assert_eq!(g.get(2, 1), Some(&None));
+        assert_eq!(g.get(3, 0), None);
+        assert_eq!(g.get(0, -1), None);
+        assert_eq!(*g.get_wrapping(-1, 3), Some(3));
+
+        let digits = g.map(|d| d.unwrap_or(0));
+        assert_eq!(digits.row(1), &[4, 5, 0]);
+        assert_eq!(digits.column(2).copied().collect::<Vec<_>>(), [3, 0, 9]);
+        assert_eq!(digits.position(|d| *d == 7), Some((0, 2)));
+        assert_eq!(digits.to_string(), "123\n450\n789\n");
+
+        let n4 = digits.neighbours4(0, 0).collect::<Vec<_>>();
+        assert_eq!(n4, [(1, 0), (0, 1)]);
+        assert_eq!(digits.neighbours8(1, 1).count(), 8);
+        assert_eq!(digits.neighbours8(2, 2).count(), 3);
+    }
+}
@@ -0,0 +1,242 @@
+// Ranges of integers, and sets of them. Intervals are half open internally, [start, end), which
+// makes lengths and adjacency come out without any +1s. Puzzles tend to give inclusive ranges,
+// so those have their own constructor.
+
+use std::ops::{Add, Sub};
+
+pub trait Bound: Copy + Ord + Add<Output = Self> + Sub<Output = Self> + From<u8> {}
+
+impl<T: Copy + Ord + Add<Output = T> + Sub<Output = T> + From<u8>> Bound for T {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Bound> Interval<T> {
+    // Everything from start up to but not including end. An end before the start is empty.
+    pub fn new(start: T, end: T) -> Interval<T> {
+        Interval {
+            start,
+            end: end.max(start),
+        }
+    }
+
+    // Everything from first to last, including both
+    pub fn inclusive(first: T, last: T) -> Interval<T> {
+        Interval::new(first, last + T::from(1))
+    }
+
+    #[allow(unused)]
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    #[allow(unused)]
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    pub fn len(&self) -> T {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn contains(&self, v: T) -> bool {
+        self.start <= v && v < self.end
+    }
+
+    pub fn contains_interval(&self, other: &Interval<T>) -> bool {
+        other.is_empty() || (self.start <= other.start && other.end <= self.end)
+    }
+
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    pub fn intersection(&self, other: &Interval<T>) -> Interval<T> {
+        Interval::new(self.start.max(other.start), self.end.min(other.end))
+    }
+}
+
+// A set of values stored as sorted, disjoint intervals. Intervals that touch are merged, so the
+// representation of any given set is unique.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T: Bound> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet { intervals: vec![] }
+    }
+
+    pub fn insert(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+
+        // Everything from the first interval that reaches the new one, to the last one that
+        // starts before it ends, gets merged into it
+        let first = self.intervals.partition_point(|i| i.end < interval.start);
+        let last = self.intervals.partition_point(|i| i.start <= interval.end);
+
+        let mut merged = interval;
+        if first < last {
+            merged.start = merged.start.min(self.intervals[first].start);
+            merged.end = merged.end.max(self.intervals[last - 1].end);
+        }
+
+        self.intervals.splice(first..last, [merged]);
+    }
+
+    #[allow(unused)]
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    // The number of values in the set
+    pub fn len(&self) -> T {
+        self.intervals
+            .iter()
+            .fold(T::from(0), |total, i| total + i.len())
+    }
+
+    pub fn contains(&self, v: T) -> bool {
+        let i = self.intervals.partition_point(|i| i.end <= v);
+        self.intervals.get(i).is_some_and(|i| i.contains(v))
+    }
+
+    #[allow(unused)]
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut out = self.clone();
+        for i in &other.intervals {
+            out.insert(*i);
+        }
+        out
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut out = vec![];
+        let (mut a, mut b) = (0, 0);
+
+        // Walks both lists together, always stepping past whichever interval ends first
+        while a < self.intervals.len() && b < other.intervals.len() {
+            let (ia, ib) = (self.intervals[a], other.intervals[b]);
+            let i = ia.intersection(&ib);
+            if !i.is_empty() {
+                out.push(i);
+            }
+
+            if ia.end < ib.end {
+                a += 1;
+            } else {
+                b += 1;
+            }
+        }
+
+        IntervalSet { intervals: out }
+    }
+
+    #[allow(unused)]
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        match self.span() {
+            Some(span) => self.intersection(&other.gaps(span)),
+            None => IntervalSet::new(),
+        }
+    }
+
+    // The smallest interval covering the whole set
+    pub fn span(&self) -> Option<Interval<T>> {
+        let first = self.intervals.first()?;
+        let last = self.intervals.last()?;
+        Some(Interval::new(first.start, last.end))
+    }
+
+    // Everything within `bounds` that isn't in the set
+    pub fn gaps(&self, bounds: Interval<T>) -> IntervalSet<T> {
+        let mut out = vec![];
+        let mut from = bounds.start;
+
+        for i in &self.intervals {
+            let gap = Interval::new(from, i.start).intersection(&bounds);
+            if !gap.is_empty() {
+                out.push(gap);
+            }
+            from = from.max(i.end);
+        }
+
+        let gap = Interval::new(from, bounds.end);
+        if !gap.is_empty() {
+            out.push(gap);
+        }
+
+        IntervalSet { intervals: out }
+    }
+}
+
+impl<T: Bound> Default for IntervalSet<T> {
+    fn default() -> IntervalSet<T> {
+        IntervalSet::new()
+    }
+}
+
+impl<T: Bound> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> IntervalSet<T> {
+        let mut out = IntervalSet::new();
+        for i in iter {
+            out.insert(i);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(intervals: &[(isize, isize)]) -> IntervalSet<isize> {
+        intervals
+            .iter()
+            .map(|&(s, e)| Interval::new(s, e))
+            .collect()
+    }
+
+    #[test]
+    fn intervals() {
+        let a = Interval::inclusive(2, 4);
+        assert_eq!((a.start(), a.end(), a.len()), (2, 5, 3));
+        assert!(a.contains(4) && !a.contains(5));
+        assert!(a.contains_interval(&Interval::inclusive(3, 4)));
+        assert!(!a.contains_interval(&Interval::inclusive(3, 5)));
+        assert!(a.overlaps(&Interval::inclusive(4, 9)));
+        assert!(!a.overlaps(&Interval::new(5, 9)));
+        assert!(Interval::new(3, 1).is_empty());
+    }
+
+    #[test]
+    fn sets() {
+        let s = set(&[(10, 12), (0, 2), (4, 6), (2, 3), (5, 8), (20, 20)]);
+        assert_eq!(s, set(&[(0, 3), (4, 8), (10, 12)]));
+        assert_eq!(s.len(), 9);
+        assert!(s.contains(7) && !s.contains(8) && !s.contains(3));
+
+        let mut bridged = s.clone();
+        bridged.insert(Interval::new(3, 10));
+        assert_eq!(bridged, set(&[(0, 12)]));
+
+        let t = set(&[(2, 5), (7, 11), (14, 15)]);
+        assert_eq!(s.union(&t), set(&[(0, 12), (14, 15)]));
+        assert_eq!(s.intersection(&t), set(&[(2, 3), (4, 5), (7, 8), (10, 11)]));
+        assert_eq!(s.difference(&t), set(&[(0, 2), (5, 7), (11, 12)]));
+        assert_eq!(
+            s.gaps(Interval::new(-2, 14)),
+            set(&[(-2, 0), (3, 4), (8, 10), (12, 14)])
+        );
+        assert_eq!(s.gaps(Interval::new(5, 9)), set(&[(8, 9)]));
+    }
+}
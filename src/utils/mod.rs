@@ -1 +1,12 @@
 pub mod bit_grid;
+pub mod bit_grid3;
+pub mod branch_bound;
+pub mod cycle;
+pub mod geom;
+pub mod grid;
+pub mod interval;
+pub mod num;
+pub mod parse;
+pub mod search;
+pub mod viz;
+pub mod word;
@@ -0,0 +1,178 @@
+// Integers for the days whose values can outgrow a machine word. Arithmetic goes through the
+// Integer trait, which reports overflow as an error instead of wrapping in release builds. Int is
+// a checked isize by default, or an arbitrary precision BigInt with the bignum feature, which
+// never overflows at all.
+
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::Hash;
+use std::str::FromStr;
+
+#[cfg(not(feature = "bignum"))]
+pub type Int = isize;
+#[cfg(feature = "bignum")]
+pub type Int = num_bigint::BigInt;
+
+// The expression that failed, then what was being done at the time, innermost first
+#[derive(Debug, PartialEq)]
+pub struct Overflow {
+    pub expression: String,
+    pub context: Vec<String>,
+}
+
+pub type NResult<T> = Result<T, Overflow>;
+
+impl Overflow {
+    pub fn context(mut self, c: impl Display) -> Overflow {
+        self.context.push(c.to_string());
+        self
+    }
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} overflowed", self.expression)?;
+        for c in &self.context {
+            write!(f, ", while {c}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+pub trait Integer: Clone + Debug + Display + Ord + Hash + FromStr {
+    fn from_i64(n: i64) -> Self;
+    fn to_i64(&self) -> Option<i64>;
+
+    fn add(&self, other: &Self) -> NResult<Self>;
+    fn sub(&self, other: &Self) -> NResult<Self>;
+    fn mul(&self, other: &Self) -> NResult<Self>;
+    // Division and remainder round towards zero, like the primitives. Dividing by zero counts
+    // as overflowing.
+    fn div(&self, other: &Self) -> NResult<Self>;
+    fn rem(&self, other: &Self) -> NResult<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::from_i64(0)
+    }
+}
+
+fn overflow(a: &impl Display, op: &str, b: &impl Display) -> Overflow {
+    Overflow {
+        expression: format!("{a} {op} {b}"),
+        context: vec![],
+    }
+}
+
+macro_rules! checked_integer {
+    ($t:ty) => {
+        impl Integer for $t {
+            fn from_i64(n: i64) -> Self {
+                n.try_into().unwrap()
+            }
+
+            fn to_i64(&self) -> Option<i64> {
+                (*self).try_into().ok()
+            }
+
+            fn add(&self, other: &Self) -> NResult<Self> {
+                self.checked_add(*other)
+                    .ok_or_else(|| overflow(self, "+", other))
+            }
+
+            fn sub(&self, other: &Self) -> NResult<Self> {
+                self.checked_sub(*other)
+                    .ok_or_else(|| overflow(self, "-", other))
+            }
+
+            fn mul(&self, other: &Self) -> NResult<Self> {
+                self.checked_mul(*other)
+                    .ok_or_else(|| overflow(self, "*", other))
+            }
+
+            fn div(&self, other: &Self) -> NResult<Self> {
+                self.checked_div(*other)
+                    .ok_or_else(|| overflow(self, "/", other))
+            }
+
+            fn rem(&self, other: &Self) -> NResult<Self> {
+                self.checked_rem(*other)
+                    .ok_or_else(|| overflow(self, "%", other))
+            }
+        }
+    };
+}
+
+checked_integer!(isize);
+checked_integer!(i64);
+checked_integer!(i128);
+
+#[cfg(feature = "bignum")]
+impl Integer for num_bigint::BigInt {
+    fn from_i64(n: i64) -> Self {
+        n.into()
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        self.try_into().ok()
+    }
+
+    fn add(&self, other: &Self) -> NResult<Self> {
+        Ok(self + other)
+    }
+
+    fn sub(&self, other: &Self) -> NResult<Self> {
+        Ok(self - other)
+    }
+
+    fn mul(&self, other: &Self) -> NResult<Self> {
+        Ok(self * other)
+    }
+
+    fn div(&self, other: &Self) -> NResult<Self> {
+        if other.is_zero() {
+            return Err(overflow(self, "/", other));
+        }
+        Ok(self / other)
+    }
+
+    fn rem(&self, other: &Self) -> NResult<Self> {
+        if other.is_zero() {
+            return Err(overflow(self, "%", other));
+        }
+        Ok(self % other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checked() {
+        assert_eq!(7i64.mul(&6), Ok(42));
+        assert_eq!((-7i64).div(&2), Ok(-3));
+        assert_eq!((-7i64).rem(&2), Ok(-1));
+
+        let err = i64::MAX.add(&1).unwrap_err().context("adding up");
+        assert_eq!(
+            err.to_string(),
+            "9223372036854775807 + 1 overflowed, while adding up"
+        );
+        assert!(5isize.div(&0).is_err());
+        assert_eq!(i128::from_i64(-3).to_i64(), Some(-3));
+        assert_eq!(i128::MAX.to_i64(), None);
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn bignum() {
+        let big = Int::from_i64(i64::MAX);
+        let square = big.mul(&big).unwrap();
+
+        assert_eq!(square.to_string(), "85070591730234615847396907784232501249");
+        assert_eq!(square.div(&big), Ok(big.clone()));
+        assert_eq!(square.to_i64(), None);
+        assert!(big.rem(&Int::from_i64(0)).is_err());
+    }
+}
@@ -0,0 +1,384 @@
+// Parser combinators for puzzle inputs. A parser is anything that can read a value from the
+// front of an Input, which includes plain closures, so one-off parsers need no ceremony. Errors
+// say where they happened and what was expected there, rather than just unwrapping a None.
+//
+// Failing is routine, since every losing branch of alt or the end of many fails, so an error is
+// only a byte offset and usually a static description. The line and column are worked out once
+// the error reaches parse_all.
+
+use std::borrow::Cow;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Copy, Clone)]
+pub struct Input<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Expected {
+    // A description, like "a number"
+    What(Cow<'static, str>),
+    // Exactly this text
+    Lit(&'static str),
+    Either(Box<Expected>, Box<Expected>),
+}
+
+impl From<&'static str> for Expected {
+    fn from(what: &'static str) -> Expected {
+        Expected::What(Cow::Borrowed(what))
+    }
+}
+
+impl From<String> for Expected {
+    fn from(what: String) -> Expected {
+        Expected::What(Cow::Owned(what))
+    }
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::What(what) => write!(f, "{what}"),
+            Expected::Lit(s) => write!(f, "{s:?}"),
+            Expected::Either(a, b) => write!(f, "{a} or {b}"),
+        }
+    }
+}
+
+// The location is the line and column, counting from 1 with columns in characters. It's only
+// known once the error has been through parse_all.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub expected: Expected,
+    pub location: Option<(usize, usize)>,
+}
+
+pub type PResult<T> = Result<T, ParseError>;
+
+impl ParseError {
+    fn locate(mut self, text: &str) -> ParseError {
+        let before = &text[..self.offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+        self.location = Some((line, column));
+        self
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "line {line}, column {column}: ")?,
+            None => write!(f, "byte {}: ", self.offset)?,
+        }
+        write!(f, "expected {}", self.expected)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl<'a> Input<'a> {
+    pub fn new(text: &'a str) -> Input<'a> {
+        Input { text, pos: 0 }
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    pub fn advance(&mut self, bytes: usize) {
+        self.pos += bytes;
+    }
+
+    pub fn error(&self, expected: impl Into<Expected>) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            expected: expected.into(),
+            location: None,
+        }
+    }
+}
+
+pub trait Parser<T> {
+    fn parse(&self, input: &mut Input) -> PResult<T>;
+
+    fn map<U>(self, f: impl Fn(T) -> U) -> impl Parser<U>
+    where
+        Self: Sized,
+    {
+        move |input: &mut Input| self.parse(input).map(&f)
+    }
+}
+
+impl<T, F: Fn(&mut Input) -> PResult<T>> Parser<T> for F {
+    fn parse(&self, input: &mut Input) -> PResult<T> {
+        self(input)
+    }
+}
+
+// Parses the whole of the text. Trailing whitespace, such as the final newline, is allowed.
+pub fn parse_all<T>(parser: impl Parser<T>, text: &str) -> PResult<T> {
+    let mut input = Input::new(text);
+    let out = parser.parse(&mut input).map_err(|e| e.locate(text))?;
+
+    input.advance(input.rest().len() - input.rest().trim_end().len());
+    if !input.at_end() {
+        return Err(input.error("end of input").locate(text));
+    }
+    Ok(out)
+}
+
+pub fn lit(s: &'static str) -> impl Parser<()> {
+    move |input: &mut Input| {
+        if input.rest().starts_with(s) {
+            input.advance(s.len());
+            Ok(())
+        } else {
+            Err(input.error(Expected::Lit(s)))
+        }
+    }
+}
+
+// One or more characters matching the predicate
+pub fn take_while1(pred: fn(char) -> bool, what: &'static str) -> impl Parser<String> {
+    move |input: &mut Input| {
+        let rest = input.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+
+        if len == 0 {
+            return Err(input.error(what));
+        }
+        input.advance(len);
+        Ok(rest[..len].to_string())
+    }
+}
+
+pub fn alpha() -> impl Parser<String> {
+    take_while1(|c| c.is_ascii_alphabetic(), "letters")
+}
+
+// Everything up to the end of the line, which may be nothing
+pub fn rest_of_line() -> impl Parser<String> {
+    |input: &mut Input| {
+        let rest = input.rest();
+        let len = rest.find('\n').unwrap_or(rest.len());
+        input.advance(len);
+        Ok(rest[..len].to_string())
+    }
+}
+
+// Reads the digits after an optional sign of `sign_len` bytes, straight from the input
+fn number<T: FromStr>(input: &mut Input, sign_len: usize) -> PResult<T> {
+    let rest = input.rest();
+    let digits = rest[sign_len..]
+        .bytes()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+
+    if digits == 0 {
+        let mut at = *input;
+        at.advance(sign_len);
+        return Err(at.error("a number"));
+    }
+
+    let value = rest[..sign_len + digits].parse();
+    let value = value.map_err(|_| input.error("a number in range"))?;
+    input.advance(sign_len + digits);
+    Ok(value)
+}
+
+pub fn uint<T: FromStr>() -> impl Parser<T> {
+    |input: &mut Input| number(input, 0)
+}
+
+pub fn int<T: FromStr>() -> impl Parser<T> {
+    |input: &mut Input| {
+        let sign_len = usize::from(input.rest().starts_with('-'));
+        number(input, sign_len)
+    }
+}
+
+pub fn pair<A, B>(a: impl Parser<A>, b: impl Parser<B>) -> impl Parser<(A, B)> {
+    move |input: &mut Input| Ok((a.parse(input)?, b.parse(input)?))
+}
+
+pub fn preceded<A, B>(a: impl Parser<A>, b: impl Parser<B>) -> impl Parser<B> {
+    move |input: &mut Input| {
+        a.parse(input)?;
+        b.parse(input)
+    }
+}
+
+pub fn terminated<A, B>(a: impl Parser<A>, b: impl Parser<B>) -> impl Parser<A> {
+    move |input: &mut Input| {
+        let out = a.parse(input)?;
+        b.parse(input)?;
+        Ok(out)
+    }
+}
+
+// Tries a, and if that fails tries b from the same place. When both fail, whichever got
+// furthest is probably the one that was meant, so that's the error reported.
+pub fn alt<T>(a: impl Parser<T>, b: impl Parser<T>) -> impl Parser<T> {
+    move |input: &mut Input| {
+        let start = *input;
+        let ea = match a.parse(input) {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
+
+        *input = start;
+        let eb = match b.parse(input) {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
+
+        Err(match ea.offset.cmp(&eb.offset) {
+            std::cmp::Ordering::Greater => ea,
+            std::cmp::Ordering::Less => eb,
+            std::cmp::Ordering::Equal => ParseError {
+                expected: Expected::Either(Box::new(ea.expected), Box::new(eb.expected)),
+                ..ea
+            },
+        })
+    }
+}
+
+// Zero or more, stopping at the first failure
+pub fn many<T>(item: impl Parser<T>) -> impl Parser<Vec<T>> {
+    move |input: &mut Input| {
+        let mut out = vec![];
+        loop {
+            let start = *input;
+            match item.parse(input) {
+                Ok(v) if input.pos > start.pos => out.push(v),
+                _ => {
+                    *input = start;
+                    return Ok(out);
+                }
+            }
+        }
+    }
+}
+
+// One or more items with a separator between each
+pub fn sep_by<T, S>(item: impl Parser<T>, sep: impl Parser<S>) -> impl Parser<Vec<T>> {
+    move |input: &mut Input| {
+        let mut out = vec![item.parse(input)?];
+        loop {
+            let start = *input;
+            if sep.parse(input).is_err() {
+                *input = start;
+                return Ok(out);
+            }
+            out.push(item.parse(input)?);
+        }
+    }
+}
+
+// Values separated by fixed text, like "x=1, y=2" read as template(&["x=", ", y=", ""], int())
+pub fn template<T>(parts: &'static [&'static str], item: impl Parser<T>) -> impl Parser<Vec<T>> {
+    move |input: &mut Input| {
+        let mut out = vec![];
+        for (n, part) in parts.iter().enumerate() {
+            if n > 0 {
+                out.push(item.parse(input)?);
+            }
+            lit(part).parse(input)?;
+        }
+        Ok(out)
+    }
+}
+
+// One item per line, up to a blank line or the end of the input. The newline after the last
+// line is left alone.
+pub fn lines<T>(item: impl Parser<T>) -> impl Parser<Vec<T>> {
+    sep_by(terminated(item, end_of_line()), next_line())
+}
+
+// Groups separated by blank lines
+#[allow(unused)]
+pub fn blocks<T>(block: impl Parser<T>) -> impl Parser<Vec<T>> {
+    sep_by(block, lit("\n\n"))
+}
+
+fn end_of_line() -> impl Parser<()> {
+    |input: &mut Input| {
+        if input.at_end() || input.rest().starts_with('\n') {
+            Ok(())
+        } else {
+            Err(input.error("end of line"))
+        }
+    }
+}
+
+// A newline, as long as another non blank line follows it
+fn next_line() -> impl Parser<()> {
+    |input: &mut Input| {
+        let rest = input.rest();
+        if rest.starts_with('\n') && rest.len() > 1 && !rest[1..].starts_with('\n') {
+            input.advance(1);
+            Ok(())
+        } else {
+            Err(input.error("another line"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn combinators() {
+        let point = template(&["(", ",", ")"], int::<isize>()).map(|v| (v[0], v[1]));
+        let row = sep_by(point, lit(" -> "));
+        let text = "(1,-2) -> (3,4)\n(5,6)\n\n(7,8)\n";
+
+        let parsed = parse_all(blocks(lines(row)), text).unwrap();
+        assert_eq!(
+            parsed,
+            [
+                vec![vec![(1, -2), (3, 4)], vec![(5, 6)]],
+                vec![vec![(7, 8)]]
+            ]
+        );
+
+        let word = alt(lit("north").map(|_| 'N'), lit("south").map(|_| 'S'));
+        let words = many(terminated(word, alt(lit(" "), end_of_line())));
+        assert_eq!(
+            parse_all(words, "north south north"),
+            Ok(vec!['N', 'S', 'N'])
+        );
+
+        let err = parse_all(lines(sep_by(uint::<u8>(), lit(","))), "1,2\n3,x\n").unwrap_err();
+        assert_eq!((err.offset, err.location), (6, Some((2, 3))));
+        assert_eq!(err.to_string(), "line 2, column 3: expected a number");
+
+        let err = parse_all(lines(uint::<u8>()), "1\n256").unwrap_err();
+        assert_eq!(err.expected.to_string(), "a number in range");
+
+        let err = parse_all(lines(int::<i8>()), "1\n-3 3").unwrap_err();
+        assert_eq!(err.location, Some((2, 3)));
+        assert_eq!(err.expected.to_string(), "end of line");
+
+        let options = alt(lit("ab"), lit("cd"));
+        assert_eq!(
+            parse_all(options, "x").unwrap_err().to_string(),
+            "line 1, column 1: expected \"ab\" or \"cd\""
+        );
+
+        // Errors from a bare parser aren't located yet
+        let err = int::<i8>().parse(&mut Input::new("-x")).unwrap_err();
+        assert_eq!(err.to_string(), "byte 1: expected a number");
+    }
+}
@@ -0,0 +1,350 @@
+// Shortest path searches over any graph that can be described by a successor function. Each
+// search records the cost to reach every node it visited, and optionally the node it was reached
+// from, so the actual path can be walked back out afterwards.
+//
+// Where that's kept is up to the caller. The plain searches keep both costs and parents in
+// HashMaps, so their paths can always be walked back out. The _in versions take any NodeMap,
+// such as Dense for nodes that number themselves like the cells of a grid, which is much faster
+// than hashing, and parents can be () to skip keeping them when only the costs are wanted.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::{BuildHasher, Hash};
+use std::ops::Add;
+
+pub trait NodeMap<N, V> {
+    fn get(&self, n: N) -> Option<V>;
+    fn insert(&mut self, n: N, v: V);
+}
+
+impl<N: Eq + Hash, V: Copy, S: BuildHasher> NodeMap<N, V> for HashMap<N, V, S> {
+    fn get(&self, n: N) -> Option<V> {
+        HashMap::get(self, &n).copied()
+    }
+
+    fn insert(&mut self, n: N, v: V) {
+        HashMap::insert(self, n, v);
+    }
+}
+
+// Keeps nothing, for parents when no path will be asked for
+impl<N, V> NodeMap<N, V> for () {
+    fn get(&self, _n: N) -> Option<V> {
+        None
+    }
+
+    fn insert(&mut self, _n: N, _v: V) {}
+}
+
+// A slot for each node, found by an index function that numbers them from 0 to len
+pub struct Dense<V, F> {
+    slots: Vec<Option<V>>,
+    index: F,
+}
+
+impl<V: Copy, F> Dense<V, F> {
+    pub fn new(len: usize, index: F) -> Dense<V, F> {
+        Dense {
+            slots: vec![None; len],
+            index,
+        }
+    }
+}
+
+impl<N, V: Copy, F: Fn(N) -> usize> NodeMap<N, V> for Dense<V, F> {
+    fn get(&self, n: N) -> Option<V> {
+        self.slots[(self.index)(n)]
+    }
+
+    fn insert(&mut self, n: N, v: V) {
+        self.slots[(self.index)(n)] = Some(v);
+    }
+}
+
+pub struct Paths<N, C, M = HashMap<N, C>, P = HashMap<N, N>> {
+    start: N,
+    costs: M,
+    parents: P,
+    goal: Option<N>,
+    cost: std::marker::PhantomData<C>,
+}
+
+impl<N: Copy + Eq, C: Copy, M: NodeMap<N, C>, P: NodeMap<N, N>> Paths<N, C, M, P> {
+    fn new(start: N, start_cost: C, mut costs: M, parents: P) -> Paths<N, C, M, P> {
+        costs.insert(start, start_cost);
+        Paths {
+            start,
+            costs,
+            parents,
+            goal: None,
+            cost: std::marker::PhantomData,
+        }
+    }
+
+    pub fn cost(&self, n: N) -> Option<C> {
+        self.costs.get(n)
+    }
+
+    // The goal the search stopped at, if it found one
+    #[allow(unused)]
+    pub fn goal(&self) -> Option<N> {
+        self.goal
+    }
+
+    pub fn goal_cost(&self) -> Option<C> {
+        self.cost(self.goal?)
+    }
+
+    // Every node visited, with the cost to reach it
+    #[allow(unused)]
+    pub fn costs(&self) -> &M {
+        &self.costs
+    }
+
+    // The nodes from the start to `to`, including both ends. Never there if parents were ().
+    #[allow(unused)]
+    pub fn path(&self, to: N) -> Option<Vec<N>> {
+        self.cost(to)?;
+
+        let mut path = vec![to];
+        while let Some(p) = self.parents.get(*path.last().unwrap()) {
+            path.push(p);
+        }
+
+        path.reverse();
+        (path[0] == self.start).then_some(path)
+    }
+}
+
+// Every edge costs one. The goal is checked as nodes are discovered, which is as soon as their
+// distance is known.
+#[allow(unused)]
+pub fn bfs<N, I>(
+    start: N,
+    successors: impl FnMut(N) -> I,
+    goal: impl FnMut(N) -> bool,
+) -> Paths<N, usize>
+where
+    N: Copy + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    bfs_in(start, successors, goal, HashMap::new(), HashMap::new())
+}
+
+pub fn bfs_in<N, I, M, P>(
+    start: N,
+    mut successors: impl FnMut(N) -> I,
+    mut goal: impl FnMut(N) -> bool,
+    costs: M,
+    parents: P,
+) -> Paths<N, usize, M, P>
+where
+    N: Copy + Eq,
+    I: IntoIterator<Item = N>,
+    M: NodeMap<N, usize>,
+    P: NodeMap<N, N>,
+{
+    let mut paths = Paths::new(start, 0, costs, parents);
+
+    if goal(start) {
+        paths.goal = Some(start);
+        return paths;
+    }
+
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((n, cost)) = queue.pop_front() {
+        let cost = cost + 1;
+
+        for s in successors(n) {
+            if paths.costs.get(s).is_some() {
+                continue;
+            }
+
+            paths.costs.insert(s, cost);
+            paths.parents.insert(s, n);
+
+            if goal(s) {
+                paths.goal = Some(s);
+                return paths;
+            }
+
+            queue.push_back((s, cost));
+        }
+    }
+
+    paths
+}
+
+#[allow(unused)]
+pub fn dijkstra<N, C, I>(
+    start: N,
+    successors: impl FnMut(N) -> I,
+    goal: impl FnMut(N) -> bool,
+) -> Paths<N, C>
+where
+    N: Copy + Eq + Hash,
+    C: Copy + Ord + Add<Output = C> + Default,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(start, successors, |_| C::default(), goal)
+}
+
+// The heuristic must never overestimate the remaining cost, or the path found may not be the
+// shortest. Costs start from C::default(), which is taken to be zero.
+#[allow(unused)]
+pub fn astar<N, C, I>(
+    start: N,
+    successors: impl FnMut(N) -> I,
+    heuristic: impl FnMut(N) -> C,
+    goal: impl FnMut(N) -> bool,
+) -> Paths<N, C>
+where
+    N: Copy + Eq + Hash,
+    C: Copy + Ord + Add<Output = C> + Default,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar_in(
+        start,
+        successors,
+        heuristic,
+        goal,
+        HashMap::new(),
+        HashMap::new(),
+    )
+}
+
+#[allow(unused)]
+pub fn astar_in<N, C, I, M, P>(
+    start: N,
+    mut successors: impl FnMut(N) -> I,
+    mut heuristic: impl FnMut(N) -> C,
+    mut goal: impl FnMut(N) -> bool,
+    costs: M,
+    parents: P,
+) -> Paths<N, C, M, P>
+where
+    N: Copy + Eq,
+    C: Copy + Ord + Add<Output = C> + Default,
+    I: IntoIterator<Item = (N, C)>,
+    M: NodeMap<N, C>,
+    P: NodeMap<N, N>,
+{
+    let mut paths = Paths::new(start, C::default(), costs, parents);
+
+    let mut queue = BinaryHeap::from([Queued {
+        estimate: heuristic(start),
+        cost: C::default(),
+        node: start,
+    }]);
+
+    while let Some(Queued { cost, node, .. }) = queue.pop() {
+        // A node can be queued again when a cheaper way to it turns up, leaving stale entries
+        if paths.costs.get(node).is_some_and(|c| cost > c) {
+            continue;
+        }
+
+        if goal(node) {
+            paths.goal = Some(node);
+            return paths;
+        }
+
+        for (s, edge) in successors(node) {
+            let cost = cost + edge;
+            if paths.costs.get(s).is_none_or(|c| cost < c) {
+                paths.costs.insert(s, cost);
+                paths.parents.insert(s, node);
+                queue.push(Queued {
+                    estimate: cost + heuristic(s),
+                    cost,
+                    node: s,
+                });
+            }
+        }
+    }
+
+    paths
+}
+
+// Ordered by estimate alone, lowest first, so BinaryHeap pops the most promising node
+struct Queued<N, C> {
+    estimate: C,
+    cost: C,
+    node: N,
+}
+
+impl<N, C: Ord> PartialEq for Queued<N, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl<N, C: Ord> Eq for Queued<N, C> {}
+
+impl<N, C: Ord> PartialOrd for Queued<N, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N, C: Ord> Ord for Queued<N, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.cmp(&self.estimate)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A 5x5 room with a wall down the middle, open only at the bottom
+    fn open(x: isize, y: isize) -> bool {
+        (0..5).contains(&x) && (0..5).contains(&y) && !(x == 2 && y < 4)
+    }
+
+    fn moves((x, y): (isize, isize)) -> impl Iterator<Item = (isize, isize)> {
+        [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+            .into_iter()
+            .filter(|&(x, y)| open(x, y))
+    }
+
+    #[test]
+    fn searches() {
+        let end = (4, 0);
+        let b = bfs_in((0, 0), moves, |n| n == end, HashMap::new(), HashMap::new());
+        assert_eq!(b.goal_cost(), Some(12));
+
+        let path = b.path(end).unwrap();
+        assert_eq!(path.len(), 13);
+        assert_eq!((path[0], path[12]), ((0, 0), end));
+        assert!(path.contains(&(2, 4)));
+
+        // The plain search keeps parents too, but without them there's no path, just the cost
+        assert_eq!(bfs((0, 0), moves, |n| n == end).path(end), Some(path));
+        let b = bfs_in((0, 0), moves, |n| n == end, HashMap::new(), ());
+        assert_eq!((b.goal_cost(), b.path(end)), (Some(12), None));
+        assert_eq!(b.path((0, 0)), Some(vec![(0, 0)]));
+
+        // Uneven costs, where going right is expensive
+        let weighted = |n: (isize, isize)| {
+            let cost = move |m: (isize, isize)| if m.0 > n.0 { 3 } else { 1 };
+            moves(n).map(move |m| (m, cost(m)))
+        };
+        let d = dijkstra((0, 0), weighted, |n| n == end);
+        assert_eq!(d.goal_cost(), Some(20));
+        assert_eq!(d.path(end).unwrap().len(), 13);
+
+        let manhattan =
+            |(x, y): (isize, isize)| (end.0 - x).unsigned_abs() + (end.1 - y).unsigned_abs();
+        let index = |(x, y): (isize, isize)| (y * 5 + x) as usize;
+        let (costs, parents) = (Dense::new(25, index), Dense::new(25, index));
+        let a = astar_in((0, 0), weighted, manhattan, |n| n == end, costs, parents);
+        assert_eq!(a.goal_cost(), Some(20));
+        assert_eq!(a.path(end).unwrap().len(), 13);
+
+        // Without a goal, everything reachable is visited
+        let all = bfs((0, 0), moves, |_| false);
+        assert_eq!(all.goal(), None);
+        assert_eq!(all.costs().len(), 21);
+        assert_eq!(all.cost((2, 0)), None);
+    }
+}
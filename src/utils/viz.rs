@@ -0,0 +1,517 @@
+// Rendering simulations to images, for looking at what they actually do. Anything that can draw
+// itself into a Frame can be exported, either as numbered PPM files, which are trivial to write
+// and most image tools read, or as a single looping animated GIF.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::{create_dir_all, File};
+use std::io::{stdout, BufWriter, Stdout, Write};
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+pub const GREY: Rgb = [128, 128, 128];
+pub const RED: Rgb = [220, 50, 50];
+pub const GREEN: Rgb = [60, 200, 80];
+pub const BLUE: Rgb = [70, 110, 230];
+pub const YELLOW: Rgb = [240, 200, 60];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+pub trait Visualize {
+    fn frame(&self) -> Frame;
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize, fill: Rgb) -> Frame {
+        Frame {
+            width,
+            height,
+            pixels: vec![fill; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, c: Rgb) {
+        self.pixels[y * self.width + x] = c;
+    }
+
+    // Makes each pixel a square block of pixels, so small grids are visible
+    pub fn scaled(&self, scale: usize) -> Frame {
+        let mut out = Frame::new(self.width * scale, self.height * scale, BLACK);
+        for y in 0..out.height {
+            for x in 0..out.width {
+                out.set(x, y, self.get(x / scale, y / scale));
+            }
+        }
+        out
+    }
+
+    // The part of the frame inside the given rectangle, or as much of it as there is
+    pub fn cropped(&self, x: usize, y: usize, width: usize, height: usize) -> Frame {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+
+        let mut out = Frame::new(width, height, BLACK);
+        for cy in 0..height {
+            for cx in 0..width {
+                out.set(cx, cy, self.get(x + cx, y + cy));
+            }
+        }
+        out
+    }
+
+    // Places the frame in the top left of a bigger one, for animations whose size changes
+    fn padded(&self, width: usize, height: usize, fill: Rgb) -> Frame {
+        let mut out = Frame::new(width, height, fill);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.set(x, y, self.get(x, y));
+            }
+        }
+        out
+    }
+}
+
+// Binary PPM, which is just a short text header followed by the raw pixels
+pub fn write_ppm(frame: &Frame, path: &Path) -> std::io::Result<()> {
+    let mut f = BufWriter::new(File::create(path)?);
+    write!(f, "P6\n{} {}\n255\n", frame.width, frame.height)?;
+    for p in &frame.pixels {
+        f.write_all(p)?;
+    }
+    f.flush()
+}
+
+// Animated GIF89a, looping forever, with `delay` hundredths of a second between frames. GIFs
+// are limited to a palette of 256 colours, shared by every frame here. Frames smaller than the
+// biggest are padded out with the colour of their top left pixel.
+pub fn write_gif(frames: &[Frame], delay: u16, path: &Path) -> std::io::Result<()> {
+    let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+
+    let width = frames.iter().map(|f| f.width).max().unwrap_or(0);
+    let height = frames.iter().map(|f| f.height).max().unwrap_or(0);
+    if width == 0 || height == 0 || width > 0xffff || height > 0xffff {
+        return Err(invalid("frames must be between 1 and 65535 pixels across"));
+    }
+
+    let mut palette: Vec<Rgb> = vec![];
+    let mut index = HashMap::new();
+    for p in frames.iter().flat_map(|f| &f.pixels) {
+        index.entry(*p).or_insert_with(|| {
+            palette.push(*p);
+            palette.len() - 1
+        });
+    }
+    if palette.len() > 256 {
+        return Err(invalid("more than 256 colours"));
+    }
+
+    // The palette size is stored as a power of two, of at least 2 colours
+    let bits = (1..=8).find(|b| palette.len() <= 1 << b).unwrap();
+    palette.resize(1 << bits, BLACK);
+
+    let mut f = BufWriter::new(File::create(path)?);
+    f.write_all(b"GIF89a")?;
+    f.write_all(&(width as u16).to_le_bytes())?;
+    f.write_all(&(height as u16).to_le_bytes())?;
+    f.write_all(&[0xf0 | (bits - 1), 0, 0])?;
+    for c in &palette {
+        f.write_all(c)?;
+    }
+
+    // Loop forever
+    f.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+
+    for frame in frames {
+        let frame = frame.padded(width, height, frame.pixels[0]);
+        let indices = frame
+            .pixels
+            .iter()
+            .map(|p| index[p] as u8)
+            .collect::<Vec<u8>>();
+
+        f.write_all(&[0x21, 0xf9, 4, 0])?;
+        f.write_all(&delay.to_le_bytes())?;
+        f.write_all(&[0, 0])?;
+
+        f.write_all(&[0x2c, 0, 0, 0, 0])?;
+        f.write_all(&(width as u16).to_le_bytes())?;
+        f.write_all(&(height as u16).to_le_bytes())?;
+        f.write_all(&[0])?;
+
+        let min_code_size = bits.max(2);
+        f.write_all(&[min_code_size])?;
+        for block in lzw(&indices, min_code_size).chunks(255) {
+            f.write_all(&[block.len() as u8])?;
+            f.write_all(block)?;
+        }
+        f.write_all(&[0])?;
+    }
+
+    f.write_all(&[0x3b])?;
+    f.flush()
+}
+
+// GIF's variable width LZW. Codes start one bit wider than the palette index, and grow as the
+// table does, up to 12 bits, at which point the table is thrown away and started again. The
+// decoder adds its table entries a code behind the encoder, which is why the width is checked
+// before each new entry rather than after.
+fn lzw(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut out = vec![];
+    let (mut acc, mut acc_bits) = (0u32, 0);
+    let mut emit = |code: u16, size: u8| {
+        acc |= (code as u32) << acc_bits;
+        acc_bits += size;
+        while acc_bits >= 8 {
+            out.push(acc as u8);
+            acc >>= 8;
+            acc_bits -= 8;
+        }
+    };
+
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = min_code_size + 1;
+    emit(clear, size);
+
+    let mut prefix = indices[0] as u16;
+    for &k in &indices[1..] {
+        if let Some(&code) = table.get(&(prefix, k)) {
+            prefix = code;
+            continue;
+        }
+
+        emit(prefix, size);
+        if next == 4096 {
+            emit(clear, size);
+            table.clear();
+            next = end + 1;
+            size = min_code_size + 1;
+        } else {
+            if next == 1 << size {
+                size += 1;
+            }
+            table.insert((prefix, k), next);
+            next += 1;
+        }
+        prefix = k as u16;
+    }
+
+    emit(prefix, size);
+    if next == 1 << size && size < 12 {
+        size += 1;
+    }
+    emit(end, size);
+    emit(0, 7);
+
+    out
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    Ppm,
+    Gif,
+}
+
+// Collects frames from a simulation and writes them to a directory. Only every `every`th frame
+// is kept, since most simulations run for thousands of steps.
+pub struct Exporter {
+    out: PathBuf,
+    format: Format,
+    scale: usize,
+    every: usize,
+    seen: usize,
+    exported: usize,
+    // Only GIFs hold on to frames, as they're written all at once at the end
+    frames: Vec<Frame>,
+}
+
+impl Exporter {
+    pub fn new(
+        out: &Path,
+        format: Format,
+        scale: usize,
+        every: usize,
+    ) -> std::io::Result<Exporter> {
+        assert!(scale > 0 && every > 0);
+        create_dir_all(out)?;
+
+        Ok(Exporter {
+            out: out.to_path_buf(),
+            format,
+            scale,
+            every,
+            seen: 0,
+            exported: 0,
+            frames: vec![],
+        })
+    }
+
+    pub fn push(&mut self, v: &dyn Visualize) -> std::io::Result<()> {
+        let n = self.seen;
+        self.seen += 1;
+        if !n.is_multiple_of(self.every) {
+            return Ok(());
+        }
+
+        let frame = v.frame().scaled(self.scale);
+        match self.format {
+            Format::Ppm => {
+                let path = self.out.join(format!("frame_{:05}.ppm", self.exported));
+                write_ppm(&frame, &path)?;
+            }
+            Format::Gif => self.frames.push(frame),
+        }
+        self.exported += 1;
+        Ok(())
+    }
+
+    // Writes anything still outstanding, and returns how many frames were exported
+    pub fn finish(self) -> std::io::Result<usize> {
+        if self.format == Format::Gif && !self.frames.is_empty() {
+            write_gif(&self.frames, 4, &self.out.join("animation.gif"))?;
+        }
+        Ok(self.exported)
+    }
+}
+
+// Draws a frame with ANSI true colour escapes. Each character is two pixels stacked, the upper
+// half block in the top pixel's colour on a background of the bottom one's, so cells come out
+// roughly square. Colours are only sent when they change.
+pub fn ansi(frame: &Frame) -> String {
+    let mut out = String::new();
+
+    for y in (0..frame.height).step_by(2) {
+        let (mut fg, mut bg) = (None, None);
+        for x in 0..frame.width {
+            let top = frame.get(x, y);
+            let bottom = if y + 1 < frame.height {
+                frame.get(x, y + 1)
+            } else {
+                BLACK
+            };
+
+            if fg != Some(top) {
+                let _ = write!(out, "\x1b[38;2;{};{};{}m", top[0], top[1], top[2]);
+                fg = Some(top);
+            }
+            if bg != Some(bottom) {
+                let _ = write!(out, "\x1b[48;2;{};{};{}m", bottom[0], bottom[1], bottom[2]);
+                bg = Some(bottom);
+            }
+            out.push('\u{2580}');
+        }
+        out.push_str("\x1b[0m\n");
+    }
+
+    out
+}
+
+// Plays frames in the terminal as they arrive, at up to `fps` a second, redrawing in place.
+// Frames bigger than the view are cropped to its width around their middle, and to its height
+// from the top, which is where the interesting part of each of the simulations is.
+pub struct Terminal {
+    delay: Duration,
+    every: usize,
+    steps: Option<usize>,
+    width: usize,
+    height: usize,
+    seen: usize,
+    shown: usize,
+    next: Instant,
+    out: BufWriter<Stdout>,
+}
+
+impl Terminal {
+    pub fn new(
+        fps: usize,
+        every: usize,
+        steps: Option<usize>,
+        (width, height): (usize, usize),
+    ) -> std::io::Result<Terminal> {
+        assert!(fps > 0 && every > 0);
+
+        let mut out = BufWriter::new(stdout());
+        // Clear the screen and hide the cursor
+        write!(out, "\x1b[2J\x1b[?25l")?;
+
+        Ok(Terminal {
+            delay: Duration::from_secs(1) / fps as u32,
+            every,
+            steps,
+            width,
+            height,
+            seen: 0,
+            shown: 0,
+            next: Instant::now(),
+            out,
+        })
+    }
+
+    // Frames after the step limit are dropped, though the simulation carries on regardless
+    pub fn push(&mut self, v: &dyn Visualize) -> std::io::Result<()> {
+        let n = self.seen;
+        self.seen += 1;
+        if !n.is_multiple_of(self.every) || self.steps.is_some_and(|s| self.shown >= s) {
+            return Ok(());
+        }
+
+        let frame = v.frame();
+        let x = frame.width.saturating_sub(self.width) / 2;
+        let frame = frame.cropped(x, 0, self.width, self.height);
+
+        sleep(self.next.saturating_duration_since(Instant::now()));
+        self.next = Instant::now() + self.delay;
+
+        // Home the cursor, draw over the last frame, then clear whatever it left below
+        write!(self.out, "\x1b[H{}step {n}\x1b[J", ansi(&frame))?;
+        self.out.flush()?;
+        self.shown += 1;
+        Ok(())
+    }
+
+    // Puts the cursor back, and returns how many frames were shown
+    pub fn finish(mut self) -> std::io::Result<usize> {
+        writeln!(self.out, "\x1b[0m\x1b[?25h")?;
+        self.out.flush()?;
+        Ok(self.shown)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Decodes the LZW stream again, following the decoder's side of the width rules
+    fn unlzw(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut size = min_code_size + 1;
+        let (mut pos, mut prev): (usize, Option<Vec<u8>>) = (0, None);
+        let mut out = vec![];
+
+        loop {
+            let mut code = 0;
+            for b in 0..size as usize {
+                code |= ((data[(pos + b) / 8] >> ((pos + b) % 8)) as usize & 1) << b;
+            }
+            pos += size as usize;
+
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).collect();
+                table.extend([vec![], vec![]]);
+                size = min_code_size + 1;
+                prev = None;
+                continue;
+            }
+            if code == clear + 1 {
+                return out;
+            }
+
+            let entry = match (&prev, table.get(code)) {
+                (_, Some(e)) => e.clone(),
+                (Some(p), None) => [p.clone(), vec![p[0]]].concat(),
+                (None, None) => panic!("bad code"),
+            };
+            if let Some(p) = prev {
+                if table.len() < 4096 {
+                    table.push([p, vec![entry[0]]].concat());
+                }
+            }
+            if table.len() == 1 << size && size < 12 {
+                size += 1;
+            }
+
+            out.extend(&entry);
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        // Long enough, and varied enough, to fill the table and force a clear
+        let mut indices = vec![];
+        let mut x = 1u32;
+        for n in 0..40000 {
+            x = x.wrapping_mul(1103515245).wrapping_add(12345);
+            indices.push(if n % 3 == 0 { (x >> 16) as u8 % 16 } else { 3 });
+        }
+
+        assert_eq!(unlzw(&lzw(&indices, 4), 4), indices);
+        assert_eq!(unlzw(&lzw(&[1, 1, 1, 0], 2), 2), [1, 1, 1, 0]);
+
+        let mut f = Frame::new(2, 1, BLACK);
+        f.set(1, 0, WHITE);
+        let big = f.scaled(3);
+        assert_eq!((big.width(), big.height()), (6, 3));
+        assert_eq!(big.get(3, 2), WHITE);
+    }
+
+    #[test]
+    fn terminal() {
+        let mut f = Frame::new(2, 3, BLACK);
+        f.set(0, 0, RED);
+        f.set(1, 2, WHITE);
+
+        let crop = f.cropped(1, 1, 5, 5);
+        assert_eq!((crop.width(), crop.height()), (1, 2));
+        assert_eq!(crop.get(0, 1), WHITE);
+
+        let red = "\x1b[38;2;220;50;50m";
+        let (fg, bg) = ("\x1b[38;2;0;0;0m", "\x1b[48;2;0;0;0m");
+        let white = "\x1b[38;2;255;255;255m";
+        assert_eq!(
+            ansi(&f),
+            format!(
+                "{red}{bg}\u{2580}{fg}\u{2580}\x1b[0m\n{fg}{bg}\u{2580}{white}\u{2580}\x1b[0m\n"
+            )
+        );
+    }
+
+    #[test]
+    fn export_ppm() {
+        let out = std::env::temp_dir().join(format!("aoc22_export_{}", std::process::id()));
+        let mut exporter = Exporter::new(&out, Format::Ppm, 2, 2).unwrap();
+
+        struct Red;
+        impl Visualize for Red {
+            fn frame(&self) -> Frame {
+                Frame::new(3, 1, RED)
+            }
+        }
+
+        for _ in 0..5 {
+            exporter.push(&Red).unwrap();
+        }
+        assert_eq!(exporter.finish().unwrap(), 3);
+
+        let written = std::fs::read(out.join("frame_00002.ppm")).unwrap();
+        assert!(written.starts_with(b"P6\n6 2\n255\n"));
+        assert!(!out.join("frame_00003.ppm").exists());
+        std::fs::remove_dir_all(&out).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+// The machine word a BitGrid stores its rows in. Narrow words waste less on small grids, wide
+// words do more per operation on big ones.
+
+use std::fmt::Debug;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr};
+
+pub trait Word:
+    Copy
+    + Eq
+    + Debug
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+    + BitAndAssign
+    + BitOrAssign
+    + BitXorAssign
+{
+    const BITS: usize;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn count_ones(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+    fn leading_zeros(self) -> u32;
+    fn reverse_bits(self) -> Self;
+
+    // The lowest `bits` bits set. Saturates at both ends rather than overflowing.
+    fn low_mask(bits: isize) -> Self {
+        if bits <= 0 {
+            Self::ZERO
+        } else if bits as usize >= Self::BITS {
+            !Self::ZERO
+        } else {
+            !Self::ZERO >> (Self::BITS - bits as usize) as u32
+        }
+    }
+}
+
+macro_rules! primitive_word {
+    ($t:ty) => {
+        impl Word for $t {
+            const BITS: usize = <$t>::BITS as usize;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn count_ones(self) -> u32 {
+                <$t>::count_ones(self)
+            }
+
+            fn trailing_zeros(self) -> u32 {
+                <$t>::trailing_zeros(self)
+            }
+
+            fn leading_zeros(self) -> u32 {
+                <$t>::leading_zeros(self)
+            }
+
+            fn reverse_bits(self) -> Self {
+                <$t>::reverse_bits(self)
+            }
+        }
+    };
+}
+
+primitive_word!(u64);
+primitive_word!(u128);
+
+#[cfg(feature = "wide")]
+#[allow(unused)]
+pub use wide::Wide;
+
+// A 256 bit word made of four u64 lanes. Everything except the shifts is lane by lane, which
+// the compiler turns into vector instructions without needing nightly std::simd.
+#[cfg(feature = "wide")]
+mod wide {
+    use super::Word;
+    use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr};
+
+    const LANES: usize = 4;
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    #[allow(unused)]
+    pub struct Wide([u64; LANES]);
+
+    macro_rules! lanewise {
+        ($op:ident, $f:ident, $assign:ident, $fa:ident) => {
+            impl $op for Wide {
+                type Output = Wide;
+
+                fn $f(self, other: Wide) -> Wide {
+                    let mut out = self;
+                    out.$fa(other);
+                    out
+                }
+            }
+
+            impl $assign for Wide {
+                fn $fa(&mut self, other: Wide) {
+                    for (a, b) in self.0.iter_mut().zip(other.0) {
+                        a.$fa(b);
+                    }
+                }
+            }
+        };
+    }
+
+    lanewise!(BitAnd, bitand, BitAndAssign, bitand_assign);
+    lanewise!(BitOr, bitor, BitOrAssign, bitor_assign);
+    lanewise!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+
+    impl Not for Wide {
+        type Output = Wide;
+
+        fn not(self) -> Wide {
+            Wide(self.0.map(|l| !l))
+        }
+    }
+
+    // Lane 0 holds the lowest bits, so shifting left moves bits towards higher lanes
+    impl Shl<u32> for Wide {
+        type Output = Wide;
+
+        fn shl(self, n: u32) -> Wide {
+            let (lanes, bits) = (n as usize / 64, n % 64);
+            let mut out = [0; LANES];
+
+            for (i, o) in out.iter_mut().enumerate().skip(lanes) {
+                *o = self.0[i - lanes] << bits;
+                if bits != 0 && i > lanes {
+                    *o |= self.0[i - lanes - 1] >> (64 - bits);
+                }
+            }
+
+            Wide(out)
+        }
+    }
+
+    impl Shr<u32> for Wide {
+        type Output = Wide;
+
+        fn shr(self, n: u32) -> Wide {
+            let (lanes, bits) = (n as usize / 64, n % 64);
+            let mut out = [0; LANES];
+
+            for (i, o) in out.iter_mut().enumerate().take(LANES - lanes) {
+                *o = self.0[i + lanes] >> bits;
+                if bits != 0 && i + lanes + 1 < LANES {
+                    *o |= self.0[i + lanes + 1] << (64 - bits);
+                }
+            }
+
+            Wide(out)
+        }
+    }
+
+    impl Word for Wide {
+        const BITS: usize = 64 * LANES;
+        const ZERO: Self = Wide([0; LANES]);
+        const ONE: Self = Wide([1, 0, 0, 0]);
+
+        fn count_ones(self) -> u32 {
+            self.0.iter().map(|l| l.count_ones()).sum()
+        }
+
+        fn trailing_zeros(self) -> u32 {
+            match self.0.iter().position(|l| *l != 0) {
+                Some(i) => i as u32 * 64 + self.0[i].trailing_zeros(),
+                None => Self::BITS as u32,
+            }
+        }
+
+        fn leading_zeros(self) -> u32 {
+            match self.0.iter().rposition(|l| *l != 0) {
+                Some(i) => (LANES - 1 - i) as u32 * 64 + self.0[i].leading_zeros(),
+                None => Self::BITS as u32,
+            }
+        }
+
+        fn reverse_bits(self) -> Self {
+            let mut out = self.0.map(|l| l.reverse_bits());
+            out.reverse();
+            Wide(out)
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn matches_u128_pairs() {
+            let lo: u128 = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+            let hi: u128 = 0xdead_beef_0000_ffff_8000_0001_1234_5678;
+            let w = Wide([lo as u64, (lo >> 64) as u64, hi as u64, (hi >> 64) as u64]);
+            let halves = |w: Wide| {
+                let l = w.0[0] as u128 | (w.0[1] as u128) << 64;
+                let h = w.0[2] as u128 | (w.0[3] as u128) << 64;
+                (l, h)
+            };
+
+            for n in 1..128 {
+                assert_eq!(halves(w << n), (lo << n, hi << n | lo >> (128 - n)));
+                assert_eq!(halves(w >> n), (lo >> n | hi << (128 - n), hi >> n));
+            }
+            assert_eq!(halves(w << 128), (0, lo));
+            assert_eq!(halves(w >> 128), (hi, 0));
+            assert_eq!(
+                halves(w.reverse_bits()),
+                (hi.reverse_bits(), lo.reverse_bits())
+            );
+            assert_eq!(w.count_ones(), lo.count_ones() + hi.count_ones());
+            assert_eq!((w << 200).trailing_zeros(), 200 + lo.trailing_zeros());
+            assert_eq!((w >> 200).leading_zeros(), 200 + hi.leading_zeros());
+        }
+    }
+}